@@ -0,0 +1,8 @@
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> std::process::ExitCode {
+    tokei_toukei::server::main()
+}
+
+/// the server is not built for the web view
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -0,0 +1,160 @@
+use clap::ValueEnum;
+use serde_json::json;
+use tokei::Sort;
+
+use crate::host::{
+    statistics::{LanguageRow, Skipped, Statistics, SubmoduleStatistics},
+    submodule::{Submodule, SubmoduleMode},
+    RepositoryHost,
+};
+
+/// the rows and their order are the same as the table of the web view
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default, ValueEnum)]
+pub enum Format {
+    Json,
+    Csv,
+    #[default]
+    Table,
+}
+
+impl Format {
+    pub const HEADER: [&'static str; 6] = ["Language", "Files", "Lines", "Code", "Comments", "Blanks"];
+
+    /// the separate submodules are also rendered as the tables, like the collapsible sections of the web view
+    pub fn render(&self, statistics: &Statistics, order_by: Option<Sort>, submodules: Option<SubmoduleMode>) -> String {
+        match self {
+            Self::Json => format!("{:#}\n", Self::json(statistics, order_by)),
+            Self::Csv => Self::csv(&statistics.rows(order_by)),
+            Self::Table => Self::tables(statistics, order_by, submodules == Some(SubmoduleMode::Separate)),
+        }
+    }
+
+    fn tables(statistics: &Statistics, order_by: Option<Sort>, separate: bool) -> String {
+        let mut tables = Self::table(&statistics.rows(order_by));
+        for SubmoduleStatistics { submodule, statistics } in statistics.submodules.iter().filter(|_| separate) {
+            let short_sha: String = submodule.sha.chars().take(7).collect();
+            match statistics {
+                Ok(statistics) => {
+                    tables += &format!("\n{} @ {short_sha}\n", submodule.path);
+                    tables += &Self::tables(statistics, order_by, separate);
+                }
+                Err(err) => tables += &format!("\n{} @ {short_sha} is skipped: {err}\n", submodule.path),
+            }
+        }
+        tables
+    }
+
+    fn cells(row: &LanguageRow) -> [String; 6] {
+        let LanguageRow { language_type, files, lines, code, comments, blanks } = row;
+        let numbers = [files, lines, code, comments, blanks].map(ToString::to_string);
+        let [files, lines, code, comments, blanks] = numbers;
+        [language_type.to_string(), files, lines, code, comments, blanks]
+    }
+
+    /// the language is left-aligned and the numbers are right-aligned
+    pub fn table(rows: &[LanguageRow]) -> String {
+        let cells: Vec<_> = rows.iter().map(Self::cells).collect();
+        let widths: [usize; 6] =
+            std::array::from_fn(|j| cells.iter().map(|row| row[j].len()).fold(Self::HEADER[j].len(), usize::max));
+        let line = |row: &[&str]| -> String {
+            let cells = row.iter().zip(widths).enumerate().map(|(j, (cell, width))| match j {
+                0 => format!("{cell:<width$}"),
+                _ => format!("{cell:>width$}"),
+            });
+            cells.collect::<Vec<_>>().join("  ") + "\n"
+        };
+        let rows = cells.iter().map(|row| line(&row.each_ref().map(String::as_str)));
+        std::iter::once(line(&Self::HEADER)).chain(rows).collect()
+    }
+
+    /// the cells that contain a comma, a quote, or a newline are quoted
+    pub fn csv(rows: &[LanguageRow]) -> String {
+        let quote = |cell: &str| match cell.contains([',', '"', '\n']) {
+            true => format!("\"{}\"", cell.replace('"', "\"\"")),
+            false => cell.to_string(),
+        };
+        let rows = rows.iter().map(|row| Self::cells(row).map(|cell| quote(&cell)).join(",") + "\n");
+        std::iter::once(Self::HEADER.join(",") + "\n").chain(rows).collect()
+    }
+
+    pub fn json(statistics: &Statistics, order_by: Option<Sort>) -> serde_json::Value {
+        let Statistics { repository, commit, truncated, submodules, skipped, failures, .. } = statistics;
+        let languages: Vec<_> = statistics
+            .rows(order_by)
+            .into_iter()
+            .map(|LanguageRow { language_type, files, lines, code, comments, blanks }| {
+                json!({
+                    "language": language_type.to_string(),
+                    "files": files,
+                    "lines": lines,
+                    "code": code,
+                    "comments": comments,
+                    "blanks": blanks,
+                })
+            })
+            .collect();
+        let submodules: Vec<_> = submodules
+            .iter()
+            .map(|SubmoduleStatistics { submodule, statistics }| {
+                let (statistics, error) = match statistics {
+                    Ok(statistics) => (Some(Self::json(statistics, order_by)), None),
+                    Err(err) => (None, Some(err)),
+                };
+                let Submodule { path, url, sha, .. } = submodule;
+                json!({ "path": path, "url": url, "sha": sha, "statistics": statistics, "error": error })
+            })
+            .collect();
+        let skipped: Vec<_> = skipped
+            .iter()
+            .map(|Skipped { path, reason }| json!({ "path": path, "reason": reason.to_string() }))
+            .collect();
+        json!({
+            "repository": repository.to_url().map(|url| url.to_string()).ok(),
+            "reference": commit.reference,
+            "sha": commit.sha,
+            "truncated": truncated,
+            "languages": languages,
+            "submodules": submodules,
+            "skipped": skipped,
+            "failures": failures,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokei::LanguageType;
+
+    use super::*;
+
+    fn rows() -> Vec<LanguageRow> {
+        vec![
+            LanguageRow {
+                language_type: LanguageType::Rust,
+                files: 12,
+                lines: 1520,
+                code: 1234,
+                comments: 96,
+                blanks: 190,
+            },
+            LanguageRow { language_type: LanguageType::Cpp, files: 1, lines: 8, code: 6, comments: 0, blanks: 2 },
+        ]
+    }
+
+    #[test]
+    fn test_table() {
+        let expected = [
+            "Language  Files  Lines  Code  Comments  Blanks",
+            "Rust         12   1520  1234        96     190",
+            "C++           1      8     6         0       2",
+            "",
+        ];
+        assert_eq!(Format::table(&rows()), expected.join("\n"));
+    }
+
+    #[test]
+    fn test_csv() {
+        let expected = ["Language,Files,Lines,Code,Comments,Blanks", "Rust,12,1520,1234,96,190", "C++,1,8,6,0,2", ""];
+        assert_eq!(Format::csv(&rows()), expected.join("\n"));
+    }
+}
@@ -0,0 +1,242 @@
+pub mod format;
+pub mod policy;
+
+use std::{collections::HashMap, path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+use futures::executor::block_on;
+use url::Url;
+
+use crate::{
+    components::query_parameters::{QueryParams, StatisticsParamsModel, TableViewParamsModel},
+    error::{cli::CliError, Result},
+    github::repository::GitHubToken,
+    host::{
+        fetch::{Fetcher, NativeFetcher},
+        instance::{HostKind, Instance, Instances},
+        statistics::{Statistics, WalkOptions},
+        Repository, RepositoryHost,
+    },
+};
+
+use self::{format::Format, policy::Policy};
+
+/// count the lines of code of a remote repository, with the same statistics as the web view
+#[derive(Debug, Clone, Eq, PartialEq, Parser)]
+#[command(name = "tokei-toukei", version, about)]
+pub struct Args {
+    /// `{host}:{owner}/{repo}` such as `github:hayas1/tokei-toukei`, or the url of the repository
+    pub target: String,
+
+    /// branch, tag, or commit SHA, the head of the default branch if it is not given
+    #[arg(long)]
+    pub sha: Option<String>,
+
+    /// glob patterns of the paths to be counted
+    #[arg(long, num_args = 1..)]
+    pub paths: Vec<String>,
+
+    /// glob patterns of the paths not to be counted
+    #[arg(long, num_args = 1..)]
+    pub excluded: Vec<String>,
+
+    /// `merged` or `separate`, the submodules are not walked if it is not given
+    #[arg(long)]
+    pub submodules: Option<String>,
+
+    /// `skip` or `follow`
+    #[arg(long)]
+    pub symlinks: Option<String>,
+
+    /// `disregard` or `respect` the `.tokeignore`, `.ignore`, and `.gitignore` of the tree
+    #[arg(long)]
+    pub ignore_files: Option<String>,
+
+    /// `files`, `lines`, `code`, `comments`, or `blanks`, in descending order
+    #[arg(long)]
+    pub order_by: Option<String>,
+
+    /// files over this size in bytes are skipped without being fetched
+    #[arg(long)]
+    pub max_file_size: Option<u64>,
+
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    pub format: Format,
+
+    /// personal access token of github.com
+    #[arg(long, env = "GITHUB_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+
+    /// self-hosted instance as `{kind}={origin}`, where the kind is `gitea`, `github`, or `gitlab`, and the domain of
+    /// the origin is used as the host of the target such as `git.corp.example:group/project`
+    #[arg(long, value_name = "KIND=ORIGIN")]
+    pub instance: Vec<String>,
+
+    /// personal access token of the instances
+    #[arg(long, env = "INSTANCE_TOKEN", hide_env_values = true)]
+    pub instance_token: Option<String>,
+
+    /// TOML file of the rules, the violations are printed instead of the statistics, and the exit code is non-zero
+    #[arg(long)]
+    pub policy: Option<PathBuf>,
+}
+
+impl Args {
+    /// the arguments are parsed as the query parameters of the web view, so that they have the same semantics
+    pub fn query(&self) -> Vec<(String, String)> {
+        let Self { sha, paths, excluded, submodules, symlinks, ignore_files, order_by, .. } = self;
+        let single = [
+            ("sha", sha),
+            ("submodules", submodules),
+            ("symlinks", symlinks),
+            ("ignore_files", ignore_files),
+            ("order_by", order_by),
+        ];
+        let single = single.into_iter().filter_map(|(key, value)| Some((key, value.clone()?)));
+        let multiple =
+            paths.iter().map(|path| ("paths", path.clone())).chain(excluded.iter().map(|e| ("excluded", e.clone())));
+        single.chain(multiple).map(|(key, value)| (key.to_string(), value)).collect()
+    }
+
+    pub fn params(&self) -> Result<(StatisticsParamsModel, TableViewParamsModel)> {
+        let query = self.query();
+        Ok((StatisticsParamsModel::from_query(&query)?, TableViewParamsModel::from_query(&query)?))
+    }
+
+    /// the instances are named by the domain of their origin
+    pub fn instances(&self) -> Result<Instances> {
+        let token = self.instance_token.as_deref().map(GitHubToken::new);
+        let instances = self.instance.iter().map(|instance| {
+            let invalid = || anyhow::anyhow!(CliError::InvalidInstance(instance.clone()));
+            let (kind, origin) = instance.split_once('=').ok_or_else(invalid)?;
+            let kind = HostKind::ALL.into_iter().find(|k| k.name() == kind).ok_or_else(invalid)?;
+            let origin = Url::parse(origin).map_err(|_| invalid())?;
+            let name = origin.host_str().ok_or_else(invalid)?.to_string();
+            Ok(Instance::new(&name, kind, origin).with_token(token.clone()))
+        });
+        Ok(Instances::new(instances.collect::<Result<_>>()?))
+    }
+
+    /// nested namespaces of GitLab are also supported, such as `gitlab:group/subgroup/project`
+    pub fn repository(&self) -> Result<Repository> {
+        let invalid = || anyhow::anyhow!(CliError::InvalidTarget(self.target.clone()));
+        let instances = self.instances()?;
+        let repository = match Url::parse(&self.target) {
+            Ok(url) if url.has_host() => instances.from_url(&url)?,
+            _ => {
+                let (host, path) = self.target.split_once(':').ok_or_else(invalid)?;
+                let (owner, repo) = path.trim_matches('/').rsplit_once('/').ok_or_else(invalid)?;
+                instances.repository(host, owner, repo.trim_end_matches(".git"))?
+            }
+        };
+        let token = self.token.as_deref().map(GitHubToken::new);
+        Ok(repository.with_token(token).with_fetcher(Fetcher::Native(NativeFetcher)))
+    }
+}
+
+/// the persistent cache is not available on the command line
+pub async fn run(args: &Args) -> Result<Statistics> {
+    let (params, _) = args.params()?;
+    get(args, params.sha.as_deref()).await
+}
+
+async fn get(args: &Args, sha: Option<&str>) -> Result<Statistics> {
+    let (params, _) = args.params()?;
+    let options = WalkOptions { max_file_size: args.max_file_size, cache: false, ..params.walk_options() };
+    let (repository, config) = (args.repository()?, tokei::Config::default());
+    Statistics::get(repository, sha, &params.filter()?, &config, options).await
+}
+
+/// the baselines are counted with the same options as the target, the output is empty if there are no violations
+pub async fn check(args: &Args, policy: &Policy) -> Result<(String, bool)> {
+    let statistics = run(args).await?;
+    let mut baselines = HashMap::new();
+    for reference in policy.baselines() {
+        baselines.insert(reference, get(args, Some(reference)).await?);
+    }
+    let violations = policy.evaluate(&statistics, |reference| baselines.get(reference))?;
+    let output: String = violations.iter().map(|violation| format!("{violation}\n")).collect();
+    Ok((output, violations.is_empty()))
+}
+
+pub fn main() -> ExitCode {
+    let args = Args::parse();
+    if let Some(path) = &args.policy {
+        let checked = Policy::read(path).and_then(|policy| block_on(check(&args, &policy)));
+        return match checked {
+            Ok((output, passed)) => {
+                print!("{output}");
+                if passed {
+                    ExitCode::SUCCESS
+                } else {
+                    ExitCode::FAILURE
+                }
+            }
+            Err(err) => {
+                eprintln!("error: {err}");
+                ExitCode::FAILURE
+            }
+        };
+    }
+    let rendered = block_on(run(&args)).and_then(|statistics| {
+        let (params, TableViewParamsModel { order_by, .. }) = args.params()?;
+        if statistics.truncated {
+            eprintln!("warning: the tree of {} is truncated", statistics.repository.to_url()?);
+        }
+        if !statistics.failures.is_empty() {
+            eprintln!("warning: {} files could not be fetched", statistics.failures.len());
+        }
+        Ok(args.format.render(&statistics, order_by, params.submodules))
+    });
+    match rendered {
+        Ok(output) => {
+            print!("{output}");
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokei::Sort;
+
+    use super::*;
+    use crate::{gitlab::repository::GitLabRepository, host::submodule::SubmoduleMode};
+
+    fn parse(args: &[&str]) -> Args {
+        Args::parse_from([&["tokei-toukei"], args].concat())
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = parse(&["github:hayas1/tokei-toukei", "--sha", "v1", "--paths", "src/**", "tests/**"]);
+        assert_eq!(args.format, Format::Table);
+        let (params, table) = args.params().unwrap();
+        assert_eq!(params.sha.as_deref(), Some("v1"));
+        assert_eq!(params.paths, vec!["src/**", "tests/**"]);
+        assert_eq!(table.order_by, None);
+        assert_eq!(args.repository().unwrap().to_url().unwrap().as_str(), "https://github.com/hayas1/tokei-toukei");
+
+        let args = parse(&["gitlab:group/subgroup/project", "--submodules", "merged", "--order-by", "code"]);
+        let (params, table) = args.params().unwrap();
+        assert_eq!((params.submodules, table.order_by), (Some(SubmoduleMode::Merged), Some(Sort::Code)));
+        let expected = Repository::GitLab(GitLabRepository::new("group/subgroup", "project"));
+        assert_eq!(args.repository().unwrap(), expected.with_fetcher(Fetcher::Native(NativeFetcher)));
+
+        assert!(parse(&["github:hayas1/tokei-toukei", "--submodules", "unknown"]).params().is_err());
+        assert!(parse(&["hayas1/tokei-toukei"]).repository().is_err());
+
+        let args = parse(&["git.corp.example:group/project", "--instance", "gitlab=https://git.corp.example"]);
+        let repository = args.repository().unwrap();
+        assert_eq!(repository.to_url().unwrap().as_str(), "https://git.corp.example/group/project");
+        assert!(matches!(repository, Repository::GitLab(_)));
+        assert!(parse(&["github:hayas1/tokei-toukei", "--instance", "gitlab"]).repository().is_err());
+
+        let args = parse(&["github:hayas1/tokei-toukei", "--policy", "policy.toml"]);
+        assert_eq!(args.policy, Some(PathBuf::from("policy.toml")));
+    }
+}
@@ -0,0 +1,290 @@
+use std::{collections::BTreeSet, fmt::Display, path::Path};
+
+use serde::{de, Deserialize, Deserializer};
+use tokei::{Language, LanguageType, Sort};
+
+use crate::{
+    error::{cli::CliError, Result},
+    host::{
+        filter::PathFilter,
+        statistics::{LanguageRow, Statistics},
+    },
+};
+
+/// rules of the statistics, which are checked in CI, such as
+/// ```toml
+/// [[rules]]
+/// rule = "budget"
+/// language = "Rust"
+/// paths = ["crates/core/**"]
+/// metric = "code"
+/// max = 50000
+///
+/// [[rules]]
+/// rule = "comment_ratio"
+/// language = "Go"
+/// min = 0.1
+///
+/// [[rules]]
+/// rule = "no_new_language"
+/// baseline = "main"
+/// ```
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum Rule {
+    /// the metric of the files in the scope must not exceed `max`
+    Budget {
+        #[serde(flatten)]
+        scope: Scope,
+        metric: Sort,
+        max: usize,
+    },
+    /// the ratio of the comments to the code and the comments in the scope must be at least `min`
+    CommentRatio {
+        #[serde(flatten)]
+        scope: Scope,
+        min: f64,
+    },
+    /// languages that are not counted at the `baseline` reference must not appear
+    NoNewLanguage { baseline: String },
+}
+
+/// all languages and all files if they are not given, the paths are the same glob patterns as the web view
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+pub struct Scope {
+    #[serde(default, deserialize_with = "language")]
+    pub language: Option<LanguageType>,
+    #[serde(default)]
+    pub paths: Vec<String>,
+    #[serde(default)]
+    pub excluded: Vec<String>,
+}
+
+/// the language is written by its name such as `C++`, case-insensitively
+fn language<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<LanguageType>, D::Error> {
+    let name = Option::<String>::deserialize(deserializer)?;
+    name.map(|name| name.parse().map_err(|_| de::Error::custom(format!("unknown language `{name}`")))).transpose()
+}
+
+/// the rule is referred by its index in the policy file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub index: usize,
+    pub message: String,
+}
+impl Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rules[{}]: {}", self.index, self.message)
+    }
+}
+
+impl Policy {
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        Ok(toml::from_str(toml).map_err(|err| anyhow::anyhow!(CliError::InvalidPolicy(err.to_string())))?)
+    }
+
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_toml(&std::fs::read_to_string(path).map_err(anyhow::Error::from)?)
+    }
+
+    /// the references whose statistics are required to evaluate the rules
+    pub fn baselines(&self) -> BTreeSet<&str> {
+        let baselines = self.rules.iter().filter_map(|rule| match rule {
+            Rule::NoNewLanguage { baseline } => Some(&baseline[..]),
+            _ => None,
+        });
+        baselines.collect()
+    }
+
+    /// `baseline` returns the statistics of the reference that is listed by `baselines`
+    pub fn evaluate<'a, F>(&self, statistics: &Statistics, baseline: F) -> Result<Vec<Violation>>
+    where
+        F: Fn(&str) -> Option<&'a Statistics>,
+    {
+        let mut violations = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            let mut violate = |message: String| violations.push(Violation { index, message });
+            match rule {
+                Rule::Budget { scope, metric, max } => {
+                    let value: usize = scope.rows(statistics)?.iter().map(|row| row.metric(*metric)).sum();
+                    if value > *max {
+                        violate(format!("{} of {scope} is {value}, over the budget {max}", Self::metric(*metric)));
+                    }
+                }
+                Rule::CommentRatio { scope, min } => {
+                    let rows = scope.rows(statistics)?;
+                    let (code, comments): (usize, usize) =
+                        (rows.iter().map(|r| r.code).sum(), rows.iter().map(|r| r.comments).sum());
+                    let ratio = comments as f64 / (code + comments) as f64;
+                    if code + comments > 0 && ratio < *min {
+                        violate(format!("comment ratio of {scope} is {:.1}%, under {:.1}%", ratio * 100., min * 100.));
+                    }
+                }
+                Rule::NoNewLanguage { baseline: reference } => {
+                    let Some(baseline) = baseline(reference) else {
+                        violate(format!("the statistics of `{reference}` are not available"));
+                        continue;
+                    };
+                    for language_type in statistics.languages.keys() {
+                        if !baseline.languages.contains_key(language_type) {
+                            violate(format!("{language_type} is not counted at `{reference}`"));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(violations)
+    }
+
+    fn metric(metric: Sort) -> &'static str {
+        match metric {
+            Sort::Blanks => "blanks",
+            Sort::Comments => "comments",
+            Sort::Code => "code",
+            Sort::Files => "files",
+            Sort::Lines => "lines",
+        }
+    }
+}
+
+impl Scope {
+    /// the rows of the languages in the scope, which are counted only by the reports of the matched paths
+    pub fn rows(&self, statistics: &Statistics) -> Result<Vec<LanguageRow>> {
+        let filter = PathFilter::new(&self.paths, &self.excluded)?;
+        let languages =
+            statistics.languages.iter().filter(|(t, _)| self.language.is_none_or(|language| language == **t));
+        let rows = languages.map(|(&language_type, language)| {
+            let mut scoped = Language::new();
+            let reports = language.reports.iter().filter(|report| filter.is_included(&report.name, false));
+            reports.for_each(|report| scoped.add_report(report.clone()));
+            scoped.total();
+            LanguageRow::new(language_type, &scoped)
+        });
+        Ok(rows.collect())
+    }
+}
+impl Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.language {
+            Some(language_type) => write!(f, "{language_type}")?,
+            None => write!(f, "all languages")?,
+        }
+        if !self.paths.is_empty() {
+            write!(f, " under {}", self.paths.join(", "))?;
+        }
+        if !self.excluded.is_empty() {
+            write!(f, " excluding {}", self.excluded.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokei::{Languages, Report};
+
+    use super::*;
+    use crate::{
+        github::repository::GitHubRepository,
+        host::{Commit, Repository},
+    };
+
+    fn statistics(files: &[(&str, LanguageType, &str)]) -> Statistics {
+        let (config, mut languages) = (tokei::Config::default(), Languages::new());
+        for &(path, language_type, text) in files {
+            let mut report = Report::new(path.into());
+            report.stats = language_type.parse_from_slice(text.as_bytes(), &config);
+            languages.entry(language_type).or_default().add_report(report);
+        }
+        languages.iter_mut().for_each(|(_, language)| language.total());
+        let repository = Repository::GitHub(GitHubRepository::new("hayas1", "tokei-toukei"));
+        let commit = Commit { reference: "v1".into(), sha: "4a1e7c3d".into(), message: "Release v1".into() };
+        let (submodules, skipped, failures) = (Vec::new(), Vec::new(), Vec::new());
+        let classified = Vec::new();
+        Statistics { repository, commit, truncated: false, languages, classified, submodules, skipped, failures }
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        let policy = Policy::from_toml(
+            r#"
+            [[rules]]
+            rule = "budget"
+            language = "c++"
+            paths = ["crates/core/**"]
+            metric = "code"
+            max = 50000
+
+            [[rules]]
+            rule = "no_new_language"
+            baseline = "main"
+            "#,
+        )
+        .unwrap();
+        let scope =
+            Scope { language: Some(LanguageType::Cpp), paths: vec!["crates/core/**".into()], ..Default::default() };
+        assert_eq!(policy.rules[0], Rule::Budget { scope, metric: Sort::Code, max: 50000 });
+        assert_eq!(policy.baselines(), BTreeSet::from(["main"]));
+
+        assert!(Policy::from_toml("[[rules]]\nrule = \"budget\"\nlanguage = \"Klingon\"\nmetric = \"code\"\nmax = 1")
+            .is_err());
+        assert!(Policy::from_toml("[[rules]]\nrule = \"unknown\"").is_err());
+    }
+
+    #[test]
+    fn test_evaluate_policy() {
+        let statistics = statistics(&[
+            ("crates/core/lib.rs", LanguageType::Rust, "fn main() {\n    println!();\n}\n"),
+            ("crates/cli/main.rs", LanguageType::Rust, "// cli\nfn main() {}\n"),
+            ("server/main.go", LanguageType::Go, "package main\n\nfunc main() {}\n"),
+        ]);
+        let baseline = self::statistics(&[("crates/core/lib.rs", LanguageType::Rust, "fn main() {}\n")]);
+        let policy = Policy::from_toml(
+            r#"
+            [[rules]]
+            rule = "budget"
+            language = "Rust"
+            paths = ["crates/core/**"]
+            metric = "code"
+            max = 3
+
+            [[rules]]
+            rule = "budget"
+            metric = "files"
+            max = 2
+
+            [[rules]]
+            rule = "comment_ratio"
+            language = "Go"
+            min = 0.1
+
+            [[rules]]
+            rule = "comment_ratio"
+            excluded = ["*.go"]
+            min = 0.1
+
+            [[rules]]
+            rule = "no_new_language"
+            baseline = "main"
+            "#,
+        )
+        .unwrap();
+        let violations = policy.evaluate(&statistics, |reference| (reference == "main").then_some(&baseline)).unwrap();
+        let messages: Vec<_> = violations.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "rules[1]: files of all languages is 3, over the budget 2",
+                "rules[2]: comment ratio of Go is 0.0%, under 10.0%",
+                "rules[4]: Go is not counted at `main`",
+            ]
+        );
+    }
+}
@@ -7,6 +7,7 @@ use super::{
     darkmode::{NavIconDarkmode, Theme},
     forms::RepoUrlBar,
     routes::GoHome,
+    settings::NavIconSettings,
     REPOSITORY,
 };
 
@@ -44,7 +45,10 @@ pub fn navbar() -> HtmlResult {
                 <div class={classes!("inline-block", "invisible", "md:visible", "px-8", "shrink", "w-full", "max-w-screen-md")}>
                     <RepoUrlBar/>
                 </div>
-                <div class={classes!("inline-block", "text-center", "flex", "items-end")}>
+                <div class={classes!("inline-block", "text-center", "flex", "items-end", "gap-4")}>
+                    <div class={classes!("text-teal-200", "text-sm")}>
+                        <NavIconSettings/>
+                    </div>
                     <div class={classes!("text-teal-200", "text-sm")}>
                         <NavIconDarkmode/>
                     </div>
@@ -8,10 +8,11 @@ use yew_router::hooks::use_navigator;
 use super::{
     query_parameters::{QueryParams, StatisticsParamsModel},
     routes::Route,
+    settings::InstancesConfig,
 };
 use crate::{
     error::{render::Unreachable, Result},
-    github::repository::GitHubRepository,
+    host::RepositoryHost,
 };
 
 /// TODO refactor, with_label
@@ -30,12 +31,13 @@ pub fn repo_url_bar(#[prop_or_default] id: &String) -> HtmlResult {
                     .cast::<HtmlInputElement>()
                     .ok_or_else(|| anyhow::anyhow!(Unreachable::DomMaybeChanged))?
                     .value();
+                let InstancesConfig { instances } = InstancesConfig::get();
                 let repository = if url.is_empty() {
-                    GitHubRepository::from_url(&Url::parse(placeholder).map_err(anyhow::Error::from)?)?
+                    instances.from_url(&Url::parse(placeholder).map_err(anyhow::Error::from)?)?
                 } else {
-                    GitHubRepository::from_url(&Url::parse(&url).map_err(anyhow::Error::from)?)?
+                    instances.from_url(&Url::parse(&url).map_err(anyhow::Error::from)?)?
                 };
-                let (host, GitHubRepository { owner, repo }) = (repository.host(), repository);
+                let (host, owner, repo) = (repository.host(), repository.owner(), repository.repo());
                 Ok(Route::Statistics { host, owner, repo })
             })();
             match (navigator.clone(), route) {
@@ -87,9 +89,12 @@ pub fn repo_info_forms() -> HtmlResult {
 
     let (host_input, owner_input, repo_input) = (use_node_ref(), use_node_ref(), use_node_ref());
     let (sha_input, paths_input, excluded_input) = (use_node_ref(), use_node_ref(), use_node_ref());
+    let (submodules_input, symlinks_input, ignore_files_input) = (use_node_ref(), use_node_ref(), use_node_ref());
     let statistics = {
         let (host_input, owner_input, repo_input) = (host_input.clone(), owner_input.clone(), repo_input.clone());
         let (sha_input, paths_input, excluded_input) = (sha_input.clone(), paths_input.clone(), excluded_input.clone());
+        let (submodules_input, symlinks_input, ignore_files_input) =
+            (submodules_input.clone(), symlinks_input.clone(), ignore_files_input.clone());
         Callback::from(move |_| {
             let result: Result<(Route, StatisticsParamsModel)> = (|| {
                 let host = host_input
@@ -119,14 +124,30 @@ pub fn repo_info_forms() -> HtmlResult {
                     .cast::<HtmlInputElement>()
                     .ok_or_else(|| anyhow::anyhow!(Unreachable::DomMaybeChanged))?
                     .value();
-                let paths = if paths.is_empty() { Vec::new() } else { vec![paths] };
+                let paths = split_patterns(&paths);
                 // let paths = vec!["/src".to_string(), "/test".to_string()];
                 let excluded = excluded_input
                     .cast::<HtmlInputElement>()
                     .ok_or_else(|| anyhow::anyhow!(Unreachable::DomMaybeChanged))?
                     .value();
-                let excluded = if excluded.is_empty() { Vec::new() } else { vec![excluded] };
-                Ok((Route::Statistics { host, owner, repo }, StatisticsParamsModel { sha, paths, excluded }))
+                let excluded = split_patterns(&excluded);
+                let submodules = submodules_input
+                    .cast::<HtmlInputElement>()
+                    .ok_or_else(|| anyhow::anyhow!(Unreachable::DomMaybeChanged))?
+                    .value();
+                let submodules = if submodules.is_empty() { None } else { Some(submodules.parse()?) };
+                let symlinks = symlinks_input
+                    .cast::<HtmlInputElement>()
+                    .ok_or_else(|| anyhow::anyhow!(Unreachable::DomMaybeChanged))?
+                    .value();
+                let symlinks = if symlinks.is_empty() { None } else { Some(symlinks.parse()?) };
+                let ignore_files = ignore_files_input
+                    .cast::<HtmlInputElement>()
+                    .ok_or_else(|| anyhow::anyhow!(Unreachable::DomMaybeChanged))?
+                    .value();
+                let ignore_files = if ignore_files.is_empty() { None } else { Some(ignore_files.parse()?) };
+                let params = StatisticsParamsModel { sha, paths, excluded, submodules, symlinks, ignore_files };
+                Ok((Route::Statistics { host, owner, repo }, params))
             })();
             match (navigator.clone(), result) {
                 (None, _) => gloo::console::error!("Navigator is not available"),
@@ -146,7 +167,7 @@ pub fn repo_info_forms() -> HtmlResult {
             host_input,
             "host-input",
             "Host",
-            "Repository host",
+            "Repository host such as github, gitlab, codeberg, or a registered instance",
             "https://github.com",
             false,
             IconId::OcticonsMarkGithub16,
@@ -166,7 +187,7 @@ pub fn repo_info_forms() -> HtmlResult {
             paths_input,
             "paths-input",
             "Paths",
-            "Paths of the repository to get statistics",
+            "Paths of the repository to get statistics (gitignore-style patterns, comma separated)",
             "/",
             false,
             IconId::OcticonsFileDirectoryOpenFill16,
@@ -175,11 +196,38 @@ pub fn repo_info_forms() -> HtmlResult {
             excluded_input,
             "excluded-input",
             "Excluded",
-            "Excluded paths of the repository to get statistics",
-            "",
+            "Excluded paths of the repository to get statistics (gitignore-style patterns, comma separated)",
+            "*.md, docs/**",
             false,
             IconId::OcticonsSkip16,
         ),
+        (
+            submodules_input,
+            "submodules-input",
+            "Submodules",
+            "Walk the submodules and report them merged into the statistics or as separate sections",
+            "merged or separate",
+            false,
+            IconId::OcticonsFileSubmodule16,
+        ),
+        (
+            symlinks_input,
+            "symlinks-input",
+            "Symlinks",
+            "Skip the symlinks, or follow them within the repository without counting the same file twice",
+            "skip or follow",
+            false,
+            IconId::OcticonsFileSymlinkFile16,
+        ),
+        (
+            ignore_files_input,
+            "ignore-files-input",
+            "Ignore files",
+            "Respect .tokeignore, .ignore, and .gitignore at each directory like tokei, or count all files of the tree",
+            "disregard or respect",
+            false,
+            IconId::OcticonsEyeClosed16,
+        ),
     ];
 
     Ok(html! {
@@ -220,6 +268,11 @@ pub fn repo_info_forms() -> HtmlResult {
     })
 }
 
+/// split comma separated patterns such as `src/**, tests/**`
+pub fn split_patterns(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|pattern| !pattern.is_empty()).map(str::to_string).collect()
+}
+
 #[autoprops]
 #[function_component(BaseInfoForm)]
 pub fn base_info_form(
@@ -271,3 +324,15 @@ pub fn statistics_button(onclick: Callback<MouseEvent>, children: &Children) ->
         </button>
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_patterns() {
+        assert_eq!(split_patterns(""), Vec::<String>::new());
+        assert_eq!(split_patterns("services/billing/**"), vec!["services/billing/**"]);
+        assert_eq!(split_patterns(" src/**, ,tests/** "), vec!["src/**", "tests/**"]);
+    }
+}
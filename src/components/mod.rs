@@ -2,11 +2,16 @@ pub mod background;
 pub mod darkmode;
 pub mod forms;
 pub mod home;
+pub mod preflight;
 pub mod query_parameters;
 pub mod routes;
+pub mod settings;
 pub mod statistics;
 
 pub const REPOSITORY: &str = "https://github.com/hayas1/tokei-toukei";
 pub const BASENAME: &str = "/tokei-toukei/"; // TODO do not hard code basename
 
 pub const STORAGE_KEY_DARKMODE: &str = concat!(env!("CARGO_PKG_NAME"), "/cfg/darkmode");
+pub const STORAGE_KEY_GITHUB_TOKEN: &str = concat!(env!("CARGO_PKG_NAME"), "/cfg/github/token");
+pub const STORAGE_KEY_PREFLIGHT: &str = concat!(env!("CARGO_PKG_NAME"), "/cfg/preflight");
+pub const STORAGE_KEY_INSTANCES: &str = concat!(env!("CARGO_PKG_NAME"), "/cfg/instances");
@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_autoprops::autoprops;
+use yew_icons::{Icon, IconId};
+
+use crate::host::preflight::{Preflight, Volume};
+
+use super::{query_parameters::StatisticsParamsModel, settings::PreflightConfig};
+
+/// the count with thousands separators such as `4,312`
+pub fn format_count(count: usize) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(c);
+    }
+    formatted
+}
+
+/// the bytes in binary units such as `380 MB`
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let (mut size, mut unit) = (bytes as f64, 0);
+    while size >= 1024. && unit < UNITS.len() - 1 {
+        size /= 1024.;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{bytes} {}", UNITS[0]),
+        _ if size < 10. => format!("{size:.1} {}", UNITS[unit]),
+        _ => format!("{size:.0} {}", UNITS[unit]),
+    }
+}
+
+/// the size is unknown if the tree does not have it, such as the trees of GitLab
+pub fn format_volume(&Volume { files, bytes, unknown }: &Volume) -> String {
+    match unknown {
+        0 => format!("{} files / {}", format_count(files), format_bytes(bytes)),
+        _ if unknown == files => format!("{} files / size unknown", format_count(files)),
+        _ => format!("{} files / {} and size unknown", format_count(files), format_bytes(bytes)),
+    }
+}
+
+#[autoprops]
+#[function_component(PreflightView)]
+pub fn preflight_view(
+    preflight: &Arc<Preflight>,
+    params: &StatisticsParamsModel,
+    max_file_size: Option<u64>,
+    onconfirm: &Callback<Option<u64>>,
+) -> HtmlResult {
+    let max_file_size = use_state(|| max_file_size);
+    let max_file_size_input = use_node_ref();
+
+    let config = Default::default();
+    let estimate = match params.filter() {
+        Ok(filter) => preflight.estimate(&filter, *max_file_size, &config),
+        Err(err) => return Ok(html! { format!("error occurred: {err:?}") }),
+    };
+
+    let onchange = {
+        let (max_file_size, max_file_size_input) = (max_file_size.clone(), max_file_size_input.clone());
+        Callback::from(move |_| {
+            if let Some(input) = max_file_size_input.cast::<HtmlInputElement>() {
+                let megabytes = input.value().trim().parse::<u64>().ok();
+                max_file_size.set(megabytes.map(|mb| mb * PreflightConfig::MEGABYTE));
+            }
+        })
+    };
+    let onclick = {
+        let (max_file_size, onconfirm) = (max_file_size.clone(), onconfirm.clone());
+        Callback::from(move |_| onconfirm.emit(*max_file_size))
+    };
+
+    let mut languages: Vec<_> = estimate.languages.iter().collect();
+    languages.sort_by_key(|(_, volume)| std::cmp::Reverse(volume.bytes));
+    Ok(html! {
+        <div class={classes!("flex", "flex-col", "gap-4", "p-4", "max-w-xl", "mx-auto")}>
+            <p class={classes!("flex", "items-center", "text-lg")}>
+                <Icon icon_id={IconId::OcticonsAlert16} class={classes!("inline-block", "mr-2")}/>
+                { format!("{}, continue?", format_volume(&estimate.total)) }
+            </p>
+            if preflight.truncated {
+                <p class={classes!("text-sm", "text-teal-900/50", "dark:text-teal-50/50")}>
+                    {"The tree is truncated, so the repository may be even larger."}
+                </p>
+            }
+            <table class={classes!("table-auto", "text-sm")}>
+                <tbody>
+                    { for languages.into_iter().map(|(language_type, volume)| html! {
+                        <tr>
+                            <td class={classes!("pr-4")}>{ language_type.to_string() }</td>
+                            <td class={classes!("text-right")}>{ format_volume(volume) }</td>
+                        </tr>
+                    }) }
+                </tbody>
+            </table>
+            <div class={classes!("flex", "items-center", "gap-2", "text-sm")}>
+                <label for="preflight-max-file-size">{"skip files larger than (MB)"}</label>
+                <input ref={max_file_size_input} id="preflight-max-file-size" type="number" min="0"
+                    placeholder="no limit" {onchange}
+                    value={max_file_size.map(|bytes| (bytes / PreflightConfig::MEGABYTE).to_string()).unwrap_or_default()}
+                    class={classes!(
+                        "appearance-none", "border", "border-teal-700", "bg-white", "dark:bg-teal-800", "w-24",
+                        "placeholder-teal-600/30", "dark:placeholder-teal-50/30", "rounded-lg", "p-1", "focus:outline-none"
+                    )}
+                />
+                if estimate.oversized > 0 {
+                    <span>{ format!("{} files skipped", format_count(estimate.oversized)) }</span>
+                }
+                if max_file_size.is_some() && estimate.total.unknown > 0 {
+                    <span>{ format!("{} files of unknown size are checked after fetched", format_count(estimate.total.unknown)) }</span>
+                }
+            </div>
+            <div class={classes!("flex", "justify-end")}>
+                <button {onclick}
+                    class={classes!("px-2", "rounded-full", "border", "border-teal-600", "bg-teal-600", "text-white")}
+                >
+                    {"Continue"}
+                </button>
+            </div>
+        </div>
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_count(0), "0");
+        assert_eq!(format_count(4312), "4,312");
+        assert_eq!(format_count(1_234_567), "1,234,567");
+
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(380 * 1024 * 1024), "380 MB");
+
+        assert_eq!(format_volume(&Volume { files: 2, bytes: 1536, unknown: 0 }), "2 files / 1.5 KB");
+        assert_eq!(format_volume(&Volume { files: 2, bytes: 0, unknown: 2 }), "2 files / size unknown");
+        assert_eq!(format_volume(&Volume { files: 2, bytes: 1536, unknown: 1 }), "2 files / 1.5 KB and size unknown");
+    }
+}
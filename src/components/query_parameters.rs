@@ -1,6 +1,12 @@
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 
-use crate::error::{convert::Unreachable, Result};
+use crate::{
+    error::{convert::Unreachable, Result},
+    host::{
+        filter::PathFilter, ignores::IgnoreFilesMode, linguist::Category, statistics::WalkOptions,
+        submodule::SubmoduleMode, symlink::SymlinkMode,
+    },
+};
 use serde::{de::DeserializeOwned, Deserialize, Serialize, Serializer};
 use tokei::Sort;
 
@@ -13,8 +19,29 @@ pub struct StatisticsParamsModel {
     pub paths: Vec<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub excluded: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "option_as_vec")]
+    pub submodules: Option<SubmoduleMode>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "option_as_vec")]
+    pub symlinks: Option<SymlinkMode>,
+    #[serde(skip_serializing_if = "Option::is_none", with = "option_as_vec")]
+    pub ignore_files: Option<IgnoreFilesMode>,
 }
 impl QueryParams for StatisticsParamsModel {}
+impl StatisticsParamsModel {
+    pub fn filter(&self) -> Result<PathFilter> {
+        PathFilter::new(&self.paths, &self.excluded)
+    }
+
+    /// `max_file_size` is given by the settings
+    pub fn walk_options(&self) -> WalkOptions {
+        WalkOptions {
+            submodules: self.submodules,
+            symlinks: self.symlinks.unwrap_or_default(),
+            ignore_files: self.ignore_files.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
 
 #[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
 #[serde(default)]
@@ -25,8 +52,25 @@ pub struct TableViewParamsModel {
         serialize_with = "serialize_option_sort_as_vec"
     )]
     pub order_by: Option<Sort>,
+    /// the categories of the linguist attributes whose visibility is toggled from the default of GitHub
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub linguist: Vec<Category>,
 }
 impl QueryParams for TableViewParamsModel {}
+impl TableViewParamsModel {
+    pub fn shown(&self) -> BTreeSet<Category> {
+        Category::shown(&self.linguist)
+    }
+
+    /// the category is removed from the query if it is already toggled, so that the query is kept short
+    pub fn toggle(&self, category: Category) -> Self {
+        let mut linguist: Vec<_> = self.linguist.iter().filter(|&&c| c != category).copied().collect();
+        if linguist.len() == self.linguist.len() {
+            linguist.push(category);
+        }
+        Self { linguist, ..self.clone() }
+    }
+}
 // TODO implement Serialize for tokei::Sort
 pub fn serialize_option_sort_as_vec<S>(value: &Option<Sort>, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -104,7 +148,7 @@ pub trait QueryParams: Serialize + DeserializeOwned {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
+    use std::collections::{BTreeSet, HashMap};
 
     #[test]
     fn test_into_query() {
@@ -112,6 +156,9 @@ mod tests {
             sha: Some("master".to_string()),
             paths: vec!["/src".to_string(), "/test".to_string()],
             excluded: vec![],
+            submodules: None,
+            symlinks: None,
+            ignore_files: None,
         };
         let query = params.into_query().unwrap();
         assert_eq!(
@@ -137,14 +184,34 @@ mod tests {
             StatisticsParamsModel {
                 sha: Some("main".to_string()),
                 paths: vec!["/src".to_string(), "/test".to_string()],
-                excluded: vec![]
+                excluded: vec![],
+                submodules: None,
+                symlinks: None,
+                ignore_files: None,
             }
         );
     }
 
+    #[test]
+    fn test_submodules_query() {
+        let query = vec![("submodules".to_string(), "separate".to_string())];
+        let params = StatisticsParamsModel::from_query(&query).unwrap();
+        assert_eq!(params.submodules, Some(SubmoduleMode::Separate));
+        assert_eq!(params.into_query().unwrap(), query);
+    }
+
+    #[test]
+    fn test_ignore_files_query() {
+        let query = vec![("ignore_files".to_string(), "respect".to_string())];
+        let params = StatisticsParamsModel::from_query(&query).unwrap();
+        assert_eq!(params.walk_options().ignore_files, IgnoreFilesMode::Respect);
+        assert_eq!(params.into_query().unwrap(), query);
+        assert_eq!(StatisticsParamsModel::default().walk_options().ignore_files, IgnoreFilesMode::Disregard);
+    }
+
     #[test]
     fn test_table_view_params() {
-        let target = TableViewParamsModel { order_by: Some(Sort::Code) };
+        let target = TableViewParamsModel { order_by: Some(Sort::Code), ..Default::default() };
 
         let query = target.into_query().unwrap();
         assert_eq!(query, vec![("order_by".to_string(), "code".to_string())]);
@@ -152,4 +219,16 @@ mod tests {
         let params = TableViewParamsModel::from_query(&query).unwrap();
         assert_eq!(params, target);
     }
+
+    #[test]
+    fn test_linguist_params() {
+        let query = vec![("linguist".to_string(), "generated".to_string())];
+        let params = TableViewParamsModel::from_query(&query).unwrap();
+        assert_eq!(params.shown(), BTreeSet::from([Category::Generated, Category::Overridden]));
+        assert_eq!(params.into_query().unwrap(), query);
+
+        let toggled = params.toggle(Category::Overridden).toggle(Category::Generated);
+        assert_eq!(toggled.linguist, vec![Category::Overridden]);
+        assert_eq!(toggled.shown(), BTreeSet::new());
+    }
 }
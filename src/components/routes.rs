@@ -6,6 +6,7 @@ use super::{
     background::{Background, Navbar, Screen},
     darkmode::Theme,
     home::HomePage,
+    settings::InstancesConfig,
     statistics::StatisticsPage,
     BASENAME,
 };
@@ -24,7 +25,12 @@ impl Route {
     pub fn switch(self) -> Html {
         match self {
             Route::Home => html! { <HomePage/> },
-            Route::Statistics { host, owner, repo } => html! { <StatisticsPage {host} {owner} {repo}/> },
+            Route::Statistics { host, owner, repo } => {
+                match InstancesConfig::get().instances.repository(&host, &owner, &repo) {
+                    Ok(repository) => html! { <StatisticsPage {repository}/> },
+                    Err(_) => html! { <UnsupportedHost {host}/> },
+                }
+            }
             Route::NotFound => html! { <NotFound/> },
         }
     }
@@ -74,6 +80,21 @@ pub fn not_found() -> HtmlResult {
     })
 }
 
+#[autoprops]
+#[function_component(UnsupportedHost)]
+pub fn unsupported_host(host: &String) -> HtmlResult {
+    let navigator = use_navigator();
+    Ok(html! {
+        <div>
+            <h1>{ "Unsupported Host" }</h1>
+            <p>{ format!("`{host}` is not a supported repository host") }</p>
+            <GoHome navigator={navigator}>
+                <span>{ "Go Home" }</span>
+            </GoHome>
+        </div>
+    })
+}
+
 #[autoprops]
 #[function_component(RouterUnavailable)]
 pub fn router_unavailable() -> HtmlResult {
@@ -0,0 +1,471 @@
+use gloo::storage::{LocalStorage, Storage};
+use serde::{Deserialize, Serialize};
+use url::Url;
+use wasm_bindgen_futures::spawn_local;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+use yew_autoprops::autoprops;
+use yew_icons::{Icon, IconId};
+
+use crate::{
+    error::{render::Unreachable, Result},
+    github::repository::GitHubToken,
+    host::{
+        cache::{Cache, CacheUsage},
+        instance::{HostKind, Instance, Instances},
+    },
+};
+
+use super::{
+    preflight::{format_bytes, format_count},
+    STORAGE_KEY_GITHUB_TOKEN, STORAGE_KEY_INSTANCES, STORAGE_KEY_PREFLIGHT,
+};
+
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct TokenConfig {
+    pub token: Option<GitHubToken>,
+}
+impl TokenConfig {
+    fn read_local_storage() -> Option<String> {
+        LocalStorage::get(STORAGE_KEY_GITHUB_TOKEN).ok()
+    }
+
+    fn write_local_storage(&self) -> Result<()> {
+        match &self.token {
+            Some(token) => LocalStorage::set(STORAGE_KEY_GITHUB_TOKEN, token.secret()).map_err(anyhow::Error::from)?,
+            None => LocalStorage::delete(STORAGE_KEY_GITHUB_TOKEN),
+        }
+        Ok(())
+    }
+
+    pub fn new(token: &str) -> Self {
+        let token = token.trim();
+        Self { token: (!token.is_empty()).then(|| GitHubToken::new(token)) }
+    }
+
+    pub fn get() -> Self {
+        Self { token: Self::read_local_storage().map(|token| GitHubToken::new(&token)) }
+    }
+
+    pub fn save(&self) -> Result<&Self> {
+        Self::write_local_storage(self)?;
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct InstancesConfig {
+    pub instances: Instances,
+}
+impl InstancesConfig {
+    fn read_local_storage() -> Option<Instances> {
+        LocalStorage::get(STORAGE_KEY_INSTANCES).ok()
+    }
+
+    fn write_local_storage(&self) -> Result<()> {
+        match self.instances.registered.is_empty() {
+            false => LocalStorage::set(STORAGE_KEY_INSTANCES, &self.instances).map_err(anyhow::Error::from)?,
+            true => LocalStorage::delete(STORAGE_KEY_INSTANCES),
+        }
+        Ok(())
+    }
+
+    pub fn new(instances: Instances) -> Self {
+        Self { instances }
+    }
+
+    pub fn get() -> Self {
+        Self { instances: Self::read_local_storage().unwrap_or_default() }
+    }
+
+    pub fn save(&self) -> Result<&Self> {
+        Self::write_local_storage(self)?;
+        Ok(self)
+    }
+}
+
+/// the walk is confirmed before fetching the blobs if the estimated size exceeds the threshold
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PreflightConfig {
+    pub threshold: u64,
+    pub max_file_size: Option<u64>,
+}
+impl Default for PreflightConfig {
+    fn default() -> Self {
+        Self { threshold: Self::DEFAULT_THRESHOLD, max_file_size: None }
+    }
+}
+impl PreflightConfig {
+    pub const MEGABYTE: u64 = 1024 * 1024;
+    pub const DEFAULT_THRESHOLD: u64 = 100 * Self::MEGABYTE;
+
+    fn read_local_storage() -> Option<Self> {
+        LocalStorage::get(STORAGE_KEY_PREFLIGHT).ok()
+    }
+
+    fn write_local_storage(&self) -> Result<()> {
+        match self == &Self::default() {
+            false => LocalStorage::set(STORAGE_KEY_PREFLIGHT, self).map_err(anyhow::Error::from)?,
+            true => LocalStorage::delete(STORAGE_KEY_PREFLIGHT),
+        }
+        Ok(())
+    }
+
+    /// the sizes are given in megabytes, empty threshold means the default and empty max file size means no limit
+    pub fn new(threshold: &str, max_file_size: &str) -> Result<Self> {
+        let megabytes = |s: &str| -> Result<Option<u64>> {
+            match s.trim() {
+                "" => Ok(None),
+                s => Ok(Some(s.parse::<u64>().map_err(anyhow::Error::from)? * Self::MEGABYTE)),
+            }
+        };
+        let threshold = megabytes(threshold)?.unwrap_or(Self::DEFAULT_THRESHOLD);
+        Ok(Self { threshold, max_file_size: megabytes(max_file_size)? })
+    }
+
+    pub fn get() -> Self {
+        Self::read_local_storage().unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<&Self> {
+        Self::write_local_storage(self)?;
+        Ok(self)
+    }
+}
+
+#[autoprops]
+#[function_component(NavIconSettings)]
+pub fn nav_icon_settings() -> HtmlResult {
+    let dropdown = use_state(|| false);
+    let apparent = {
+        let dropdown = dropdown.clone();
+        Callback::from(move |_| dropdown.set(true))
+    };
+    let hidden = {
+        let dropdown = dropdown.clone();
+        Callback::from(move |_| dropdown.set(false))
+    };
+
+    Ok(html! {
+        <div>
+            <button onclick={apparent}>
+                <Icon icon_id={IconId::OcticonsGear16} title={"Settings"}/>
+            </button>
+            if *dropdown {
+                <div onclick={hidden} class={classes!("flex", "justify-end", "absolute",
+                    "top-0", "left-0", "w-full", "h-full", "min-w-screen", "min-h-screen"
+                )}>
+                    <div onclick={Callback::from(|e: MouseEvent| e.stop_propagation())} class={classes!("block", "mt-14", "mx-4")}>
+                        <div class={classes!("container", "rounded-lg", "text-base", "border-2", "p-4", "w-80",
+                            "text-teal-700", "bg-teal-50", "border-teal-100",
+                            "dark:text-teal-50", "dark:bg-teal-900", "dark:border-teal-800",
+                        )}>
+                            <TokenSettings/>
+                            <hr class={classes!("my-4", "border-teal-100", "dark:border-teal-800")}/>
+                            <InstanceSettings/>
+                            <hr class={classes!("my-4", "border-teal-100", "dark:border-teal-800")}/>
+                            <PreflightSettings/>
+                            <hr class={classes!("my-4", "border-teal-100", "dark:border-teal-800")}/>
+                            <CacheSettings/>
+                        </div>
+                    </div>
+                </div>
+            }
+        </div>
+    })
+}
+
+#[autoprops]
+#[function_component(TokenSettings)]
+pub fn token_settings() -> HtmlResult {
+    let config = use_state(TokenConfig::get);
+    let token_input = use_node_ref();
+
+    let save = {
+        let (config, token_input) = (config.clone(), token_input.clone());
+        Callback::from(move |_| {
+            let saved: Result<_> = (|| {
+                let token = token_input
+                    .cast::<HtmlInputElement>()
+                    .ok_or_else(|| anyhow::anyhow!(Unreachable::DomMaybeChanged))?
+                    .value();
+                Ok(TokenConfig::new(&token).save()?.clone())
+            })();
+            match saved {
+                Ok(saved) => config.set(saved),
+                Err(err) => gloo::console::error!(err.to_string()), // TODO error handling
+            }
+        })
+    };
+    let clear = {
+        let config = config.clone();
+        Callback::from(move |_| match TokenConfig::default().save() {
+            Ok(saved) => config.set(saved.clone()),
+            Err(err) => gloo::console::error!(err.to_string()), // TODO error handling
+        })
+    };
+
+    Ok(html! {
+        <div class={classes!("flex", "flex-col", "gap-2", "text-sm")}>
+            <label for="github-token" class={classes!("flex", "items-center")}>
+                <Icon icon_id={IconId::OcticonsKey16} class={classes!("inline-block", "mr-2")}/>
+                {"GitHub token"}
+            </label>
+            <p class={classes!("text-xs", "text-teal-900/50", "dark:text-teal-50/50")}>
+                {"Personal access token for private repositories and higher rate limits, stored in this browser only."}
+            </p>
+            <input ref={token_input}
+                id="github-token"
+                type="password"
+                autocomplete="off"
+                placeholder={if config.token.is_some() { "********" } else { "github_pat_..." }}
+                class={classes!(
+                    "appearance-none", "border", "border-teal-700", "bg-white", "dark:bg-teal-800",
+                    "placeholder-teal-600/30", "dark:placeholder-teal-50/30", "rounded-lg", "p-1", "focus:outline-none"
+                )}
+            />
+            <div class={classes!("flex", "justify-end", "gap-2")}>
+                <button onclick={clear} disabled={config.token.is_none()}
+                    class={classes!("px-2", "rounded-full", "border", "border-teal-600", "disabled:opacity-30")}
+                >
+                    {"Clear"}
+                </button>
+                <button onclick={save}
+                    class={classes!("px-2", "rounded-full", "border", "border-teal-600", "bg-teal-600", "text-white")}
+                >
+                    {"Save"}
+                </button>
+            </div>
+        </div>
+    })
+}
+
+#[autoprops]
+#[function_component(InstanceSettings)]
+pub fn instance_settings() -> HtmlResult {
+    let config = use_state(InstancesConfig::get);
+    let (name_input, kind_select, origin_input) = (use_node_ref(), use_node_ref(), use_node_ref());
+    let token_input = use_node_ref();
+
+    let register = {
+        let config = config.clone();
+        let (name_input, kind_select, origin_input) = (name_input.clone(), kind_select.clone(), origin_input.clone());
+        let token_input = token_input.clone();
+        Callback::from(move |_| {
+            let saved: Result<_> = (|| {
+                let name = name_input
+                    .cast::<HtmlInputElement>()
+                    .ok_or_else(|| anyhow::anyhow!(Unreachable::DomMaybeChanged))?
+                    .value();
+                let kind = kind_select
+                    .cast::<HtmlSelectElement>()
+                    .ok_or_else(|| anyhow::anyhow!(Unreachable::DomMaybeChanged))?
+                    .selected_index();
+                let kind = usize::try_from(kind).ok().and_then(|i| HostKind::ALL.get(i)).unwrap_or(&HostKind::Gitea);
+                let origin = origin_input
+                    .cast::<HtmlInputElement>()
+                    .ok_or_else(|| anyhow::anyhow!(Unreachable::DomMaybeChanged))?
+                    .value();
+                let origin = Url::parse(origin.trim()).map_err(anyhow::Error::from)?;
+                let token = token_input
+                    .cast::<HtmlInputElement>()
+                    .ok_or_else(|| anyhow::anyhow!(Unreachable::DomMaybeChanged))?
+                    .value();
+                let TokenConfig { token } = TokenConfig::new(&token);
+                let instance = Instance::new(name.trim(), *kind, origin).with_token(token);
+                Ok(InstancesConfig::new(config.instances.clone().register(instance)?).save()?.clone())
+            })();
+            match saved {
+                Ok(saved) => config.set(saved),
+                Err(err) => gloo::console::error!(err.to_string()), // TODO error handling
+            }
+        })
+    };
+    let remove = |name: String| {
+        let config = config.clone();
+        Callback::from(move |_| match InstancesConfig::new(config.instances.clone().remove(&name)).save() {
+            Ok(saved) => config.set(saved.clone()),
+            Err(err) => gloo::console::error!(err.to_string()), // TODO error handling
+        })
+    };
+
+    let input_classes = classes!(
+        "appearance-none",
+        "border",
+        "border-teal-700",
+        "bg-white",
+        "dark:bg-teal-800",
+        "placeholder-teal-600/30",
+        "dark:placeholder-teal-50/30",
+        "rounded-lg",
+        "p-1",
+        "focus:outline-none"
+    );
+    Ok(html! {
+        <div class={classes!("flex", "flex-col", "gap-2", "text-sm")}>
+            <label for="instance-name" class={classes!("flex", "items-center")}>
+                <Icon icon_id={IconId::OcticonsServer16} class={classes!("inline-block", "mr-2")}/>
+                {"Instances"}
+            </label>
+            <p class={classes!("text-xs", "text-teal-900/50", "dark:text-teal-50/50")}>
+                {"Self-hosted instances such as GitHub Enterprise Server or GitLab, the name is used as the host of the route such as /toukei/{name}/{owner}/{repo}. The token is sent only to the instance."}
+            </p>
+            <ul>
+                { for config.instances.registered.iter().map(|instance| html! {
+                    <li class={classes!("flex", "items-center", "justify-between")}>
+                        <span class={classes!("truncate")} title={instance.origin.to_string()}>
+                            { format!("{} ({})", instance.name, instance.origin.host_str().unwrap_or_default()) }
+                        </span>
+                        <button onclick={remove(instance.name.clone())} title={"Remove"}>
+                            <Icon icon_id={IconId::OcticonsX16}/>
+                        </button>
+                    </li>
+                }) }
+            </ul>
+            <input ref={name_input} id="instance-name" type="text" placeholder="name" class={input_classes.clone()}/>
+            <select ref={kind_select} class={input_classes.clone()}>
+                { for HostKind::ALL.iter().map(|kind| html! { <option>{ kind.label() }</option> }) }
+            </select>
+            <input ref={origin_input} type="url" placeholder="https://git.example.com" class={input_classes.clone()}/>
+            <input ref={token_input} type="password" autocomplete="off" placeholder="token (optional)" class={input_classes}/>
+            <div class={classes!("flex", "justify-end", "gap-2")}>
+                <button onclick={register}
+                    class={classes!("px-2", "rounded-full", "border", "border-teal-600", "bg-teal-600", "text-white")}
+                >
+                    {"Add"}
+                </button>
+            </div>
+        </div>
+    })
+}
+
+#[autoprops]
+#[function_component(PreflightSettings)]
+pub fn preflight_settings() -> HtmlResult {
+    let config = use_state(PreflightConfig::get);
+    let (threshold_input, max_file_size_input) = (use_node_ref(), use_node_ref());
+
+    let save = {
+        let config = config.clone();
+        let (threshold_input, max_file_size_input) = (threshold_input.clone(), max_file_size_input.clone());
+        Callback::from(move |_| {
+            let saved: Result<_> = (|| {
+                let threshold = threshold_input
+                    .cast::<HtmlInputElement>()
+                    .ok_or_else(|| anyhow::anyhow!(Unreachable::DomMaybeChanged))?
+                    .value();
+                let max_file_size = max_file_size_input
+                    .cast::<HtmlInputElement>()
+                    .ok_or_else(|| anyhow::anyhow!(Unreachable::DomMaybeChanged))?
+                    .value();
+                Ok(*PreflightConfig::new(&threshold, &max_file_size)?.save()?)
+            })();
+            match saved {
+                Ok(saved) => config.set(saved),
+                Err(err) => gloo::console::error!(err.to_string()), // TODO error handling
+            }
+        })
+    };
+
+    let input_classes = classes!(
+        "appearance-none",
+        "border",
+        "border-teal-700",
+        "bg-white",
+        "dark:bg-teal-800",
+        "placeholder-teal-600/30",
+        "dark:placeholder-teal-50/30",
+        "rounded-lg",
+        "p-1",
+        "w-24",
+        "focus:outline-none"
+    );
+    let megabytes = |bytes: u64| (bytes / PreflightConfig::MEGABYTE).to_string();
+    Ok(html! {
+        <div class={classes!("flex", "flex-col", "gap-2", "text-sm")}>
+            <label for="preflight-threshold" class={classes!("flex", "items-center")}>
+                <Icon icon_id={IconId::OcticonsAlert16} class={classes!("inline-block", "mr-2")}/>
+                {"Large repositories"}
+            </label>
+            <p class={classes!("text-xs", "text-teal-900/50", "dark:text-teal-50/50")}>
+                {"Confirm before fetching if the estimated size exceeds the threshold. Files larger than the max file size are skipped."}
+            </p>
+            <div class={classes!("flex", "items-center", "justify-between")}>
+                <span>{"threshold (MB)"}</span>
+                <input ref={threshold_input} id="preflight-threshold" type="number" min="0"
+                    value={megabytes(config.threshold)} class={input_classes.clone()}
+                />
+            </div>
+            <div class={classes!("flex", "items-center", "justify-between")}>
+                <span>{"max file size (MB)"}</span>
+                <input ref={max_file_size_input} type="number" min="0" placeholder="no limit"
+                    value={config.max_file_size.map(megabytes).unwrap_or_default()} class={input_classes}
+                />
+            </div>
+            <div class={classes!("flex", "justify-end", "gap-2")}>
+                <button onclick={save}
+                    class={classes!("px-2", "rounded-full", "border", "border-teal-600", "bg-teal-600", "text-white")}
+                >
+                    {"Save"}
+                </button>
+            </div>
+        </div>
+    })
+}
+
+#[autoprops]
+#[function_component(CacheSettings)]
+pub fn cache_settings() -> HtmlResult {
+    let usage = use_state(|| None);
+    let refresh = {
+        let usage = usage.clone();
+        Callback::from(move |clear: bool| {
+            let usage = usage.clone();
+            spawn_local(async move {
+                let refreshed: Result<_> = async {
+                    let cache = Cache::open().await?;
+                    if clear {
+                        cache.clear().await?;
+                    }
+                    cache.usage().await
+                }
+                .await;
+                match refreshed {
+                    Ok(refreshed) => usage.set(Some(refreshed)),
+                    Err(err) => gloo::console::error!(err.to_string()), // TODO error handling
+                }
+            })
+        })
+    };
+    {
+        let refresh = refresh.clone();
+        use_effect_with((), move |_| refresh.emit(false));
+    }
+    let clear = Callback::from(move |_| refresh.emit(true));
+
+    Ok(html! {
+        <div class={classes!("flex", "flex-col", "gap-2", "text-sm")}>
+            <label class={classes!("flex", "items-center")}>
+                <Icon icon_id={IconId::OcticonsDatabase16} class={classes!("inline-block", "mr-2")}/>
+                {"Cache"}
+            </label>
+            <p class={classes!("text-xs", "text-teal-900/50", "dark:text-teal-50/50")}>
+                {"Files are cached by their content in this browser, so they are not fetched again for other branches or forks. API responses are cached to be revalidated without using the rate limit."}
+            </p>
+            <p>
+                { match *usage {
+                    Some(CacheUsage { entries, bytes }) => format!("{} entries / {}", format_count(entries), format_bytes(bytes)),
+                    None => "-".to_string(),
+                } }
+            </p>
+            <div class={classes!("flex", "justify-end", "gap-2")}>
+                <button onclick={clear} disabled={usage.is_none_or(|usage| usage.entries == 0)}
+                    class={classes!("px-2", "rounded-full", "border", "border-teal-600", "disabled:opacity-30")}
+                >
+                    {"Clear cache"}
+                </button>
+            </div>
+        </div>
+    })
+}
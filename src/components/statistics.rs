@@ -1,6 +1,9 @@
-use std::sync::Arc;
+use std::{rc::Rc, sync::Arc};
 
-use tokei::{Language, Sort};
+use gloo::timers::callback::{Interval, Timeout};
+use tokei::{Languages, Sort};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::spawn_local;
 use yew::{prelude::*, suspense::use_future_with};
 use yew_autoprops::autoprops;
 use yew_icons::{Icon, IconId};
@@ -9,36 +12,69 @@ use yew_router::hooks::{use_location, use_navigator, use_route};
 use super::{
     background::{Pane, ResponsivePanesFrame},
     forms::{RepoInfoForms, RepoUrlBar},
+    preflight::{format_bytes, format_count, PreflightView},
     query_parameters::{QueryParams, StatisticsParamsModel, TableViewParamsModel},
     routes::{Route, RouterUnavailable, Unreachable},
+    settings::{PreflightConfig, TokenConfig},
 };
 use crate::{
-    error::Result,
-    github::{repository::GitHubRepository, statistics::Statistics},
+    error::{repository::RateLimitError, Error, Result},
+    github::repository::GitHubRepository,
+    gitlab::repository::GitLabRepository,
+    host::{
+        control::{AbortGuard, WalkControl},
+        linguist::{Category, GitAttributes},
+        preflight::Preflight,
+        statistics::{LanguageRow, Progress, Skipped, Statistics, SubmoduleStatistics, WalkOptions},
+        submodule::SubmoduleMode,
+        Repository, RepositoryHost,
+    },
 };
 
 pub const CAPTION: &str = "Statistics";
 
+pub fn loading() -> Html {
+    html! {
+        <div class={classes!("w-full", "h-full", "flex", "justify-center", "items-center")} aria-label="Loading">
+            <div class={classes!("animate-spin", "inline-block", "w-8", "h-8", "border-4", "border-teal-600", "rounded-full", "border-t-transparent")}></div>
+        </div>
+    }
+}
+
+pub fn error_view(err: &Error, repository: &Repository, onretry: &Callback<()>) -> Html {
+    match err.downcast_ref::<RateLimitError>() {
+        Some(rate_limited) => html! {
+            <RateLimited error={rate_limited.clone()} repository={repository.clone()} onretry={onretry.clone()}/>
+        },
+        None => html! { format!("error occurred: {err:?}") },
+    }
+}
+
+pub fn host_icon(repository: &Repository) -> IconId {
+    match repository {
+        Repository::GitHub(_) => IconId::OcticonsMarkGithub16,
+        Repository::GitLab(_) | Repository::Gitea(_) => IconId::OcticonsRepo16,
+    }
+}
+
 #[autoprops]
 #[function_component(StatisticsPage)]
-pub fn statistics_page(host: &String, owner: &String, repo: &String) -> HtmlResult {
+pub fn statistics_page(repository: &Repository) -> HtmlResult {
     let Some(location) = use_location() else {
         return Ok(html! { <RouterUnavailable/> });
     };
-    let query = StatisticsParamsModel::from_query(&location.query::<Vec<(String, String)>>().unwrap());
-    let repository = Arc::new(GitHubRepository::new(owner, repo));
+    let Ok(query) = StatisticsParamsModel::from_query(&location.query::<Vec<(String, String)>>().unwrap()) else {
+        return Ok(html! { <Unreachable/> });
+    };
+    let repository = Arc::new(repository.clone().with_token(TokenConfig::get().token));
     let repository_url = repository.to_url().unwrap().to_string();
 
-    let fallback = html! {
-        <div class={classes!("w-full", "h-full", "flex", "justify-center", "items-center")} aria-label="Loading">
-            <div class={classes!("animate-spin", "inline-block", "w-8", "h-8", "border-4", "border-teal-600", "rounded-full", "border-t-transparent")}></div>
-        </div>
-    };
+    let fallback = loading();
     Ok(html! {
         <ResponsivePanesFrame>
             <Pane class={classes!("p-6", "grow", "max-w-xs", "md:w-full", "md:max-w-full")}>
                 <p>
-                    <Icon icon_id={IconId::OcticonsMarkGithub16} class={classes!("mx-2", "inline-block")}/>
+                    <Icon icon_id={host_icon(&repository)} class={classes!("mx-2", "inline-block")}/>
                     <a href={repository_url.clone()} class={classes!(
                         "border-b", "border-teal-500", "text-teal-500", "hover:text-teal-700",
                         "dark:border-teal-100", "dark:text-teal-100", "dark:hover:text-teal-200",
@@ -47,7 +83,7 @@ pub fn statistics_page(host: &String, owner: &String, repo: &String) -> HtmlResu
                     </a>
                 </p>
                 <Suspense {fallback}>
-                    <StatisticsView repository={repository}/>
+                    <StatisticsView repository={repository} params={query}/>
                 </Suspense>
             </Pane>
             <Pane class={classes!("p-6", "max-w-xs", "flex", "flex-col", "justify-start")}>
@@ -71,134 +107,539 @@ pub fn statistics_page(host: &String, owner: &String, repo: &String) -> HtmlResu
 
 #[autoprops]
 #[function_component(StatisticsView)]
-pub fn statistics_view(repository: &Arc<GitHubRepository>) -> HtmlResult {
-    let repository = repository.clone();
-    let config = Default::default();
+pub fn statistics_view(repository: &Arc<Repository>, params: &StatisticsParamsModel) -> HtmlResult {
+    let retry = use_state(|| 0);
+    let onretry = {
+        let retry = retry.clone();
+        Callback::from(move |_| retry.set(*retry + 1))
+    };
+    // the max file size that is chosen in the confirmation, which is reset by another repository or parameters
+    let confirmed = use_state(|| None);
+    {
+        let confirmed = confirmed.clone();
+        use_effect_with((repository.clone(), params.clone()), move |_| confirmed.set(None));
+    }
 
-    let result =
-        use_future_with(repository.clone(), |_| async move { repository.get_statistics(&config).await.map(Arc::new) })?;
+    // the preflight is aborted when the component is unmounted or the dependencies are changed, even if suspended
+    let guard = use_memo((repository.clone(), params.clone(), *retry), |_| AbortGuard::default());
+    let control = guard.0;
+    let result = use_future_with((repository.clone(), params.clone(), *retry), move |deps| async move {
+        let (repository, params, _) = &*deps;
+        let filter = params.filter()?;
+        let preflight =
+            Preflight::get((**repository).clone().with_control(control), params.sha.as_deref(), &filter).await?;
+        if let Some(control) = control {
+            control.release();
+        }
+        // the walk attaches its own control, so the released one is not left to the repository
+        Ok(Arc::new(Preflight { repository: preflight.repository.with_control(None), ..preflight }))
+    })?;
 
+    let PreflightConfig { threshold, max_file_size } = PreflightConfig::get();
     Ok(html! {
-        match &(*result) {
-            Ok(statistics) => html! {
-                <div class={classes!("pt-4")}>
-                    <div class={classes!("pb-2", "inline-flex", "rounded-md", "text-sm", "hidden")} role="group"> // TODO implement other than table view
-                        <button type="button"
-                            class={classes!("px-2", "border", "rounded-s-full", "hover:bg-teal-50", "hover:dark:bg-teal-800", "focus:ring-2")}
-                        >
-                            <p>{"Table"}</p>
-                        </button>
-                        <button type="button"
-                            class={classes!("px-2", "border-t", "border-b", "hover:bg-teal-50", "hover:dark:bg-teal-800", "focus:ring-2")}
-                        >
-                            <p class="text-teal-900/50 dark:text-teal-50/50" title="unimplemented">{"Heatmap"}</p>
-                        </button>
-                        <button type="button"
-                            class={classes!("px-2", "border", "rounded-e-full", "hover:bg-teal-50", "hover:dark:bg-teal-800", "focus:ring-2")}
-                        >
-                            <p class="text-teal-900/50 dark:text-teal-50/50" title="unimplemented">{"Chart"}</p>
-                        </button>
-                    </div>
-                    <div class={classes!("flex", "overflow-x-auto")}>
-                        <div class={classes!("flex-none", "w-10")}>
-                            <TableView statistics={statistics.clone()}/>
-                        </div>
-                    </div>
-                </div>
+        match (&*result, &params.filter()) {
+            (Ok(preflight), Ok(filter)) => match *confirmed {
+                None if preflight.estimate(filter, max_file_size, &Default::default()).exceeds(threshold) => {
+                    let onconfirm = Callback::from(move |max_file_size| confirmed.set(Some(max_file_size)));
+                    html! { <PreflightView preflight={preflight.clone()} params={params.clone()} {max_file_size} {onconfirm}/> }
+                }
+                confirmed => html! {
+                    <WalkView preflight={preflight.clone()} params={params.clone()}
+                        max_file_size={confirmed.unwrap_or(max_file_size)} {onretry}
+                    />
+                },
             },
-            Err(err) => html! { format!("error occurred: {err:?}") },
+            (Err(err), _) | (_, Err(err)) => error_view(err, repository, &onretry),
         }
     })
 }
 
+/// state of the walk, the partial statistics are rendered while the blobs are fetched
+#[derive(Clone)]
+pub enum Walk {
+    Pending,
+    Walking { progress: Progress, partial: Arc<Statistics> },
+    Done(Rc<Result<Arc<Statistics>>>),
+}
+impl Walk {
+    /// the partial statistics are rendered at most once in this interval, because totaling them is not cheap
+    pub const RENDER_INTERVAL_MS: f64 = 250.;
+}
+
 #[autoprops]
-#[function_component(TableView)]
-pub fn table_view(statistics: &Arc<Statistics>) -> HtmlResult {
-    fn order_by<O: Ord, F: Fn(&Language) -> O>(la: &Language, lb: &Language, f: F) -> std::cmp::Ordering {
-        f(lb).cmp(&f(la))
+#[function_component(WalkView)]
+pub fn walk_view(
+    preflight: &Arc<Preflight>,
+    params: &StatisticsParamsModel,
+    max_file_size: Option<u64>,
+    onretry: &Callback<()>,
+) -> HtmlResult {
+    let walk = use_state(|| Walk::Pending);
+    let (control, paused) = (use_state(|| None), use_state(|| false));
+    {
+        let (walk, control, paused) = (walk.clone(), control.clone(), paused.clone());
+        use_effect_with((preflight.clone(), params.clone(), max_file_size), move |deps| {
+            let (preflight, params, max_file_size) = deps.clone();
+            // the requests are aborted when the component is unmounted or the dependencies are changed
+            let walk_control = WalkControl::new().ok();
+            walk.set(Walk::Pending);
+            control.set(walk_control);
+            paused.set(false);
+            spawn_local(async move {
+                let aborted = || walk_control.is_some_and(|control| control.is_aborted());
+                let repository = preflight.repository.clone().with_control(walk_control);
+                let preflight = Preflight { repository, ..(*preflight).clone() };
+                let (config, mut rendered) = (Default::default(), f64::MIN);
+                let mut on_progress = |progress: &Progress, languages: &Languages| {
+                    let now = js_sys::Date::now();
+                    if !aborted() && now - rendered >= Walk::RENDER_INTERVAL_MS {
+                        rendered = now;
+                        let partial = Arc::new(Statistics::partial(&preflight, languages));
+                        walk.set(Walk::Walking { progress: *progress, partial });
+                    }
+                };
+                let options = WalkOptions { max_file_size, cache: true, ..params.walk_options() };
+                let result = match params.filter() {
+                    Ok(filter) => {
+                        Statistics::from_preflight_with_progress(
+                            &preflight,
+                            &filter,
+                            &config,
+                            options,
+                            &mut on_progress,
+                        )
+                        .await
+                    }
+                    Err(err) => Err(err),
+                };
+                if !aborted() {
+                    walk.set(Walk::Done(Rc::new(result.map(Arc::new))));
+                    if let Some(control) = walk_control {
+                        control.release();
+                    }
+                }
+            });
+            move || {
+                if let Some(control) = walk_control {
+                    control.abort();
+                }
+            }
+        });
+    }
+    let ontoggle = {
+        let (control, paused) = (control.clone(), paused.clone());
+        Callback::from(move |_| {
+            if let Some(control) = *control {
+                match *paused {
+                    true => control.resume(),
+                    false => control.pause(),
+                }
+                paused.set(!*paused);
+            }
+        })
+    };
+
+    Ok(html! {
+        match &*walk {
+            Walk::Pending => loading(),
+            Walk::Walking { progress, partial } => html! {
+                <>
+                    <ProgressView progress={*progress} paused={*paused} {ontoggle}/>
+                    <ResultView statistics={partial.clone()} params={params.clone()}/>
+                </>
+            },
+            Walk::Done(result) => match &**result {
+                Ok(statistics) => html! { <ResultView statistics={statistics.clone()} params={params.clone()}/> },
+                Err(err) => error_view(err, &preflight.repository, onretry),
+            },
+        }
+    })
+}
+
+#[autoprops]
+#[function_component(ProgressView)]
+pub fn progress_view(progress: &Progress, paused: bool, ontoggle: &Callback<()>) -> HtmlResult {
+    let Progress { done, total, bytes, failures, cached } = *progress;
+    let width = format!("width: {:.1}%", progress.ratio() * 100.);
+    let onclick = {
+        let ontoggle = ontoggle.clone();
+        Callback::from(move |_| ontoggle.emit(()))
+    };
+    Ok(html! {
+        <div class={classes!("pt-4", "text-sm")} role="progressbar"
+            aria-valuemin="0" aria-valuemax={total.to_string()} aria-valuenow={done.to_string()}
+        >
+            <div class={classes!("flex", "items-center", "gap-2")}>
+                <div class={classes!("grow", "h-2", "rounded-full", "bg-teal-100", "dark:bg-teal-800")}>
+                    <div class={classes!("h-2", "rounded-full", "bg-teal-600", "transition-all", paused.then_some("opacity-50"))} style={width}></div>
+                </div>
+                <button {onclick} title={if paused { "Resume" } else { "Pause" }}>
+                    <Icon icon_id={if paused { IconId::HeroiconsOutlinePlay } else { IconId::HeroiconsOutlinePause }} class={classes!("w-4", "h-4")}/>
+                </button>
+            </div>
+            <p class={classes!("pt-1", "text-teal-900/50", "dark:text-teal-50/50")}>
+                { format!("{} / {} files, {}", format_count(done), format_count(total), format_bytes(bytes)) }
+                if cached > 0 {
+                    { format!(", {} cached", format_count(cached)) }
+                }
+                if failures > 0 {
+                    <span class={classes!("text-amber-600", "dark:text-amber-400")}>
+                        { format!(", {} failed", format_count(failures)) }
+                    </span>
+                }
+                if paused {
+                    { ", paused" }
+                }
+            </p>
+        </div>
+    })
+}
+
+#[autoprops]
+#[function_component(ResultView)]
+pub fn result_view(statistics: &Arc<Statistics>, params: &StatisticsParamsModel) -> HtmlResult {
+    Ok(html! {
+        <div class={classes!("pt-4")}>
+            <CommitView statistics={statistics.clone()}/>
+            if statistics.truncated {
+                <p class={classes!("pb-2", "text-sm", "text-amber-600", "dark:text-amber-400")} role="alert">
+                    <Icon icon_id={IconId::OcticonsAlert16} class={classes!("mx-2", "inline-block")}/>
+                    {"The tree is too large to be listed entirely, so the statistics may be incomplete."}
+                </p>
+            }
+            <div class={classes!("pb-2", "inline-flex", "rounded-md", "text-sm", "hidden")} role="group"> // TODO implement other than table view
+                <button type="button"
+                    class={classes!("px-2", "border", "rounded-s-full", "hover:bg-teal-50", "hover:dark:bg-teal-800", "focus:ring-2")}
+                >
+                    <p>{"Table"}</p>
+                </button>
+                <button type="button"
+                    class={classes!("px-2", "border-t", "border-b", "hover:bg-teal-50", "hover:dark:bg-teal-800", "focus:ring-2")}
+                >
+                    <p class="text-teal-900/50 dark:text-teal-50/50" title="unimplemented">{"Heatmap"}</p>
+                </button>
+                <button type="button"
+                    class={classes!("px-2", "border", "rounded-e-full", "hover:bg-teal-50", "hover:dark:bg-teal-800", "focus:ring-2")}
+                >
+                    <p class="text-teal-900/50 dark:text-teal-50/50" title="unimplemented">{"Chart"}</p>
+                </button>
+            </div>
+            <div class={classes!("flex", "overflow-x-auto")}>
+                <div class={classes!("flex-none", "w-10")}>
+                    <TableView statistics={statistics.clone()}/>
+                </div>
+            </div>
+            <SubmodulesView statistics={statistics.clone()} merged={params.submodules == Some(SubmoduleMode::Merged)}/>
+            <SkippedView statistics={statistics.clone()}/>
+        </div>
+    })
+}
+
+#[autoprops]
+#[function_component(RateLimited)]
+pub fn rate_limited(error: &RateLimitError, repository: &Repository, onretry: &Callback<()>) -> HtmlResult {
+    // `Retry-After` is relative to the time the error is received, so the reset is recomputed only if it changes
+    let reset = *use_memo(error.clone(), |error| match error {
+        RateLimitError::Exceeded { reset } => *reset as f64 * 1000.,
+        RateLimitError::RetryAfter { seconds } => js_sys::Date::now() + *seconds as f64 * 1000.,
+    });
+    let now = use_state(js_sys::Date::now);
+    {
+        let (now, onretry) = (now.clone(), onretry.clone());
+        use_effect_with(reset, move |&reset| {
+            let interval = Interval::new(1000, move || now.set(js_sys::Date::now()));
+            let timeout = Timeout::new((reset - js_sys::Date::now()).max(0.) as u32 + 1000, move || onretry.emit(()));
+            move || drop((interval, timeout))
+        });
+    }
+
+    let date = js_sys::Date::new(&JsValue::from_f64(reset));
+    let resets_at = format!("{:02}:{:02}", date.get_hours(), date.get_minutes());
+    let remaining = ((reset - *now) / 1000.).max(0.) as u64;
+    let retry_now = {
+        let onretry = onretry.clone();
+        Callback::from(move |_| onretry.emit(()))
+    };
+
+    Ok(html! {
+        <div class={classes!("pt-4", "flex", "flex-col", "gap-2")} role="alert">
+            <p>
+                <Icon icon_id={IconId::OcticonsClock16} class={classes!("mx-2", "inline-block")}/>
+                { format!("rate limited, resets at {resets_at}") }
+            </p>
+            <p class={classes!("text-sm", "text-teal-900/50", "dark:text-teal-50/50")}>
+                { format!("retry automatically in {}:{:02}", remaining / 60, remaining % 60) }
+                if let Some(hint) = token_hint(repository) {
+                    { hint }
+                }
+            </p>
+            <div>
+                <button onclick={retry_now} class={classes!("px-2", "rounded-full", "border", "border-teal-600", "text-sm")}>
+                    { "Retry now" }
+                </button>
+            </div>
+        </div>
+    })
+}
+
+/// the token of github.com is set in the settings, and the others are registered with their instances, Gitea
+/// compatible instances are not requested with the token
+pub fn token_hint(repository: &Repository) -> Option<&'static str> {
+    match repository {
+        Repository::GitHub(GitHubRepository { token: Some(_), .. })
+        | Repository::GitLab(GitLabRepository { token: Some(_), .. })
+        | Repository::Gitea(_) => None,
+        Repository::GitHub(repository) if repository.is_github_com() => {
+            Some(", or set a GitHub token in the settings for higher rate limits")
+        }
+        Repository::GitHub(_) | Repository::GitLab(_) => {
+            Some(", or register the instance with its token in the settings for higher rate limits")
+        }
     }
+}
 
+/// merged submodules are only listed, separate ones are reported in collapsible sections
+#[autoprops]
+#[function_component(SubmodulesView)]
+pub fn submodules_view(statistics: &Arc<Statistics>, merged: bool) -> HtmlResult {
+    Ok(html! {
+        <div class={classes!("pt-2", "text-sm")}>
+            {for statistics.submodules.iter().map(|SubmoduleStatistics { submodule, statistics }| {
+                let label = format!("{} @ {}", submodule.path, submodule.sha.chars().take(7).collect::<String>());
+                match statistics {
+                    Err(err) => html! {
+                        <p class={classes!("pb-2", "text-amber-600", "dark:text-amber-400")} title={submodule.url.clone()}>
+                            <Icon icon_id={IconId::OcticonsAlert16} class={classes!("mx-2", "inline-block")}/>
+                            { format!("{label} is skipped: {err}") }
+                        </p>
+                    },
+                    Ok(statistics) if merged => html! {
+                        <>
+                            <p class={classes!("pb-2")} title={submodule.url.clone()}>
+                                <Icon icon_id={IconId::OcticonsFileSubmodule16} class={classes!("mx-2", "inline-block")}/>
+                                { format!("{label} is merged") }
+                            </p>
+                            <SubmodulesView statistics={statistics.clone()} merged={true}/>
+                        </>
+                    },
+                    Ok(statistics) => html! {
+                        <details class={classes!("pb-2")}>
+                            <summary class={classes!("cursor-pointer")} title={submodule.url.clone()}>
+                                <Icon icon_id={IconId::OcticonsFileSubmodule16} class={classes!("mx-2", "inline-block")}/>
+                                { label }
+                            </summary>
+                            <div class={classes!("pl-4")}>
+                                <CommitView statistics={statistics.clone()}/>
+                                <div class={classes!("flex", "overflow-x-auto")}>
+                                    <div class={classes!("flex-none", "w-10")}>
+                                        <TableView statistics={statistics.clone()}/>
+                                    </div>
+                                </div>
+                                <SubmodulesView statistics={statistics.clone()} merged={false}/>
+                            </div>
+                        </details>
+                    },
+                }
+            })}
+        </div>
+    })
+}
+
+#[autoprops]
+#[function_component(SkippedView)]
+pub fn skipped_view(statistics: &Arc<Statistics>) -> HtmlResult {
+    Ok(html! {
+        <>
+            if !statistics.skipped.is_empty() {
+                <details class={classes!("pt-2", "text-sm", "text-teal-900/50", "dark:text-teal-50/50")}>
+                    <summary class={classes!("cursor-pointer")}>
+                        <Icon icon_id={IconId::OcticonsSkip16} class={classes!("mx-2", "inline-block")}/>
+                        { format!("{} entries are skipped", statistics.skipped.len()) }
+                    </summary>
+                    <ul class={classes!("pl-8", "font-mono")}>
+                        {for statistics.skipped.iter().map(|Skipped { path, reason }| html! {
+                            <li>{ format!("{path} ({reason})") }</li>
+                        })}
+                    </ul>
+                </details>
+            }
+            if !statistics.failures.is_empty() {
+                <details class={classes!("pt-2", "text-sm", "text-amber-600", "dark:text-amber-400")}>
+                    <summary class={classes!("cursor-pointer")}>
+                        <Icon icon_id={IconId::OcticonsAlert16} class={classes!("mx-2", "inline-block")}/>
+                        { format!("{} files could not be fetched", statistics.failures.len()) }
+                    </summary>
+                    <ul class={classes!("pl-8", "font-mono")}>
+                        {for statistics.failures.iter().map(|failure| html! { <li>{ failure }</li> })}
+                    </ul>
+                </details>
+            }
+        </>
+    })
+}
+
+#[autoprops]
+#[function_component(CommitView)]
+pub fn commit_view(statistics: &Arc<Statistics>) -> HtmlResult {
+    let Statistics { repository, commit, .. } = &**statistics;
+    let short_sha: String = commit.sha.chars().take(7).collect();
+    let tree_url = repository.tree_url(&commit.sha).map(|url| url.to_string()).unwrap_or_default();
+    let summary = commit.message.lines().next().unwrap_or_default().to_string();
+
+    Ok(html! {
+        <p class={classes!("pb-2", "text-sm")} title={summary}>
+            <Icon icon_id={IconId::OcticonsGitCommit16} class={classes!("mx-2", "inline-block")}/>
+            <span class={classes!("pr-2")}>{ &commit.reference }</span>
+            <a href={tree_url} class={classes!(
+                "font-mono", "border-b", "border-teal-500", "text-teal-500", "hover:text-teal-700",
+                "dark:border-teal-100", "dark:text-teal-100", "dark:hover:text-teal-200",
+            )}>
+                { short_sha }
+            </a>
+        </p>
+    })
+}
+
+#[autoprops]
+#[function_component(TableView)]
+pub fn table_view(statistics: &Arc<Statistics>) -> HtmlResult {
     let Some(location) = use_location() else {
         return Ok(html! { <RouterUnavailable/> });
     };
     let query = TableViewParamsModel::from_query(&location.query::<Vec<(String, String)>>().unwrap());
-    let mut languages: Vec<_> = statistics.languages.iter().collect();
-    match query.map(|TableViewParamsModel { order_by }| order_by) {
-        Ok(Some(Sort::Blanks)) => languages.sort_by(|(_, la), (_, lb)| order_by(la, lb, |l| l.blanks)),
-        Ok(Some(Sort::Comments)) => languages.sort_by(|(_, la), (_, lb)| order_by(la, lb, |l| l.comments)),
-        Ok(Some(Sort::Code)) => languages.sort_by(|(_, la), (_, lb)| order_by(la, lb, |l| l.code)),
-        Ok(Some(Sort::Files)) => languages.sort_by(|(_, la), (_, lb)| order_by(la, lb, |l| l.reports.len())),
-        Ok(Some(Sort::Lines)) => languages.sort_by(|(_, la), (_, lb)| order_by(la, lb, |l| l.lines())),
-        Ok(None) => (),                                          // TODO ascending language name
-        Err(_) => gloo::console::warn!("Failed to parse query"), // TODO error handling
-    }
+    let table_params = match query {
+        Ok(table_params) => table_params,
+        Err(_) => {
+            gloo::console::warn!("Failed to parse query"); // TODO error handling
+            TableViewParamsModel::default()
+        }
+    };
+    let rows = statistics.rows_with(table_params.order_by, &table_params.shown());
 
     let leftmost = classes!("sticky", "left-0", "z-[50]"); // TODO long name language, z-index for scroll
     let table_header = classes!("text-teal-900", "bg-teal-50", "dark:text-teal-50", "dark:bg-teal-800");
     let (lm, th) = (leftmost.clone(), table_header.clone());
 
-    let col: [(_, _, _, Box<dyn Fn(&tokei::Language) -> _>); 6] = [
-        ("Language", None, IconId::OcticonsRocket16, Box::new(|l| l.reports.len())),
-        ("Files", Some(Sort::Files), IconId::OcticonsFile16, Box::new(|l| l.reports.len())),
-        ("Lines", Some(Sort::Lines), IconId::OcticonsThreeBars16, Box::new(|l| l.lines())),
-        ("Code", Some(Sort::Code), IconId::OcticonsCode16, Box::new(|l| l.code)),
-        ("Comments", Some(Sort::Comments), IconId::OcticonsComment16, Box::new(|l| l.comments)),
-        ("Blanks", Some(Sort::Blanks), IconId::OcticonsDash16, Box::new(|l| l.blanks)),
+    type Column = (&'static str, Option<Sort>, IconId, Box<dyn Fn(&LanguageRow) -> usize>);
+    let col: [Column; 6] = [
+        ("Language", None, IconId::OcticonsRocket16, Box::new(|r| r.files)),
+        ("Files", Some(Sort::Files), IconId::OcticonsFile16, Box::new(|r| r.files)),
+        ("Lines", Some(Sort::Lines), IconId::OcticonsThreeBars16, Box::new(|r| r.lines)),
+        ("Code", Some(Sort::Code), IconId::OcticonsCode16, Box::new(|r| r.code)),
+        ("Comments", Some(Sort::Comments), IconId::OcticonsComment16, Box::new(|r| r.comments)),
+        ("Blanks", Some(Sort::Blanks), IconId::OcticonsDash16, Box::new(|r| r.blanks)),
     ];
 
     let focused = use_state(|| None);
 
     Ok(html! {
-        <table class={classes!("table-auto")}>
-            <thead>
-                <tr>
-                    {for col.iter().enumerate().map(|(j, (title, sort, icon_id, _))| {
-                        html! {
-                            if j == 0 {
-                                <th scope="col" class={classes!(lm.clone(), th.clone())} title={&title[..]}>
-                                    <TableHeader>
-                                        <Icon icon_id={icon_id.clone()}/>
-                                    </TableHeader>
-                                </th>
-                            } else {
-                                <th scope="col" class={classes!(th.clone())} title={&title[..]}>
-                                    <TableHeaderCol focused={*focused} col={j} sort={sort.clone()} title={&title[..]}>
-                                        <Icon icon_id={icon_id.clone()}/>
-                                    </TableHeaderCol>
-                                </th>
+        <>
+            if !statistics.classified.is_empty() {
+                <LinguistToggles statistics={statistics.clone()}/>
+            }
+            <table class={classes!("table-auto")}>
+                <thead>
+                    <tr>
+                        {for col.iter().enumerate().map(|(j, (title, sort, icon_id, _))| {
+                            html! {
+                                if j == 0 {
+                                    <th scope="col" class={classes!(lm.clone(), th.clone())} title={&title[..]}>
+                                        <TableHeader>
+                                            <Icon icon_id={*icon_id}/>
+                                        </TableHeader>
+                                    </th>
+                                } else {
+                                    <th scope="col" class={classes!(th.clone())} title={&title[..]}>
+                                        <TableHeaderCol focused={*focused} col={j} sort={*sort} title={&title[..]}>
+                                            <Icon icon_id={*icon_id}/>
+                                        </TableHeaderCol>
+                                    </th>
+                                }
                             }
-                        }
-                    })}
-                </tr>
-            </thead>
-            <tbody>
-                {for languages.iter().enumerate().map(|(i, (language_type, language))| {
-                    html! {
-                        <tr class={classes!()}>
-                            {for col.iter().enumerate().map(|(j, (_, _, _, f))| {
-                                html! {
-                                    if j == 0 {
-                                        <th scope="row" class={classes!(lm.clone())}>
-                                            <TableHeaderRow class={classes!(th.clone())} focused={*focused} row={i} title={language_type.to_string()}>
-                                                { language_type.to_string() }
-                                            </TableHeaderRow>
-                                        </th>
-                                    } else {
-                                        <td>
-                                            <TableCell focused={focused.clone()} pos={(i, j)} class={classes!("text-right")}>
-                                                { f(language) }
-                                            </TableCell>
-                                        </td>
+                        })}
+                    </tr>
+                </thead>
+                <tbody>
+                    {for rows.iter().enumerate().map(|(i, row)| {
+                        html! {
+                            <tr class={classes!()}>
+                                {for col.iter().enumerate().map(|(j, (_, _, _, f))| {
+                                    html! {
+                                        if j == 0 {
+                                            <th scope="row" class={classes!(lm.clone())}>
+                                                <TableHeaderRow class={classes!(th.clone())} focused={*focused} row={i} title={row.language_type.to_string()}>
+                                                    { row.language_type.to_string() }
+                                                </TableHeaderRow>
+                                            </th>
+                                        } else {
+                                            <td>
+                                                <TableCell focused={focused.clone()} pos={(i, j)} class={classes!("text-right")}>
+                                                    { f(row) }
+                                                </TableCell>
+                                            </td>
+                                        }
                                     }
-                                }
-                            })}
-                            // <td>{ language.total() }</td>
-                        </tr>
+                                })}
+                                // <td>{ language.total() }</td>
+                            </tr>
+                            }
+                        })
+                    }
+                </tbody>
+            </table>
+        </>
+    })
+}
+
+#[autoprops]
+#[function_component(LinguistToggles)]
+pub fn linguist_toggles(statistics: &Arc<Statistics>) -> HtmlResult {
+    let (Some(navigator), Some(location), Some(route)) = (use_navigator(), use_location(), use_route::<Route>()) else {
+        return Ok(html! { <RouterUnavailable/> });
+    };
+    let Ok((statistics_params, table_params)): Result<(StatisticsParamsModel, TableViewParamsModel)> = (|| {
+        let query = location.query::<Vec<(String, String)>>().map_err(anyhow::Error::from)?;
+        Ok((StatisticsParamsModel::from_query(&query)?, TableViewParamsModel::from_query(&query)?))
+    })() else {
+        return Ok(html! { <Unreachable/> });
+    };
+    let shown = table_params.shown();
+    let toggles = Category::ALL.into_iter().filter_map(|category| {
+        let files = statistics.classified.iter().filter(|file| file.contains(category)).count();
+        (files > 0).then_some((category, files))
+    });
+
+    Ok(html! {
+        <div class={classes!("pb-2", "flex", "gap-2", "text-sm", "whitespace-nowrap")} role="group" aria-label="Linguist attributes">
+            {for toggles.map(|(category, files)| {
+                let toggle = {
+                    let (navigator, route) = (navigator.clone(), route.clone());
+                    let (statistics_params, table_params) = (statistics_params.clone(), table_params.toggle(category));
+                    Callback::from(move |_| {
+                        let param: Result<Vec<(String, String)>> = (|| {
+                            let params = [statistics_params.into_query()?, table_params.into_query()?];
+                            Ok(params.into_iter().flatten().collect())
+                        })();
+                        match param {
+                            Ok(param) => match navigator.replace_with_query(&route, &param) {
+                                Ok(_) => (),
+                                Err(err) => gloo::console::error!(err.to_string()), // TODO error handling
+                            },
+                            Err(err) => gloo::console::error!(err.to_string()), // TODO error handling
                         }
                     })
+                };
+                let pressed = shown.contains(&category);
+                let title = format!("{files} files of {}", GitAttributes::attribute(category));
+                html! {
+                    <button type="button" onclick={toggle} aria-pressed={pressed.to_string()} title={title}
+                        class={classes!("px-2", "border", "rounded-full", "hover:bg-teal-50", "hover:dark:bg-teal-800", pressed.then(|| classes!("bg-teal-200", "dark:bg-teal-700")))}
+                    >
+                        { format!("{category} ({files})") }
+                    </button>
                 }
-            </tbody>
-        </table>
+            })}
+        </div>
     })
 }
 
@@ -211,10 +652,11 @@ pub fn table_header_row(children: &Children) -> HtmlResult {
 
     let clear_order = Callback::from(move |_| {
         let param: Result<Vec<(String, String)>> = (|| {
-            let statistics_params = StatisticsParamsModel::from_query(
-                &location.query::<Vec<(String, String)>>().map_err(anyhow::Error::from)?,
-            )?;
-            let params = [statistics_params.into_query()?];
+            let query = location.query::<Vec<(String, String)>>().map_err(anyhow::Error::from)?;
+            let statistics_params = StatisticsParamsModel::from_query(&query)?;
+            // only the order is cleared, the toggled categories are kept
+            let table_params = TableViewParamsModel { order_by: None, ..TableViewParamsModel::from_query(&query)? };
+            let params = [statistics_params.into_query()?, table_params.into_query()?];
             Ok(params.into_iter().flatten().collect())
         })();
         match param {
@@ -250,12 +692,12 @@ pub fn table_header_col(
         return Ok(html! { <RouterUnavailable/> });
     };
     let Ok(statistics_params): Result<StatisticsParamsModel> = (|| {
-        Ok(StatisticsParamsModel::from_query(&location.query::<Vec<(String, String)>>().map_err(anyhow::Error::from)?)?)
+        StatisticsParamsModel::from_query(&location.query::<Vec<(String, String)>>().map_err(anyhow::Error::from)?)
     })() else {
         return Ok(html! { <Unreachable/> });
     };
     let Ok(table_params): Result<TableViewParamsModel> = (|| {
-        Ok(TableViewParamsModel::from_query(&location.query::<Vec<(String, String)>>().map_err(anyhow::Error::from)?)?)
+        TableViewParamsModel::from_query(&location.query::<Vec<(String, String)>>().map_err(anyhow::Error::from)?)
     })() else {
         return Ok(html! { <Unreachable/> });
     };
@@ -265,11 +707,10 @@ pub fn table_header_col(
 
     let order_by = {
         let (navigator, route) = (navigator.clone(), route.clone());
-        let (statistics_params, table_params) = (statistics_params, table_params);
-        let sort = sort.clone();
+        let sort = *sort;
         Callback::from(move |_| {
             let param: Result<Vec<(String, String)>> = (|| {
-                let table_params = TableViewParamsModel { order_by: sort, ..table_params };
+                let table_params = TableViewParamsModel { order_by: sort, ..table_params.clone() };
                 let params = [statistics_params.into_query()?, table_params.into_query()?];
                 Ok(params.into_iter().flatten().collect())
             })();
@@ -285,7 +726,7 @@ pub fn table_header_col(
 
     Ok(html! {
         <div class={classes!("flex", "justify-center", "relative", "bg-cover", class.clone())} title={title.clone()}>
-            <button onclick={order_by} class={classes!("px-4", "py-2", popup.then(|| "opacity-20"), order_by_this.then(|| classes!("bg-teal-200", "dark:bg-teal-700")))}>
+            <button onclick={order_by} class={classes!("px-4", "py-2", popup.then_some("opacity-20"), order_by_this.then(|| classes!("bg-teal-200", "dark:bg-teal-700")))}>
                 {children.clone()}
             </button>
             if popup {
@@ -328,8 +769,8 @@ pub fn table_cell(
         focused.map(|(i, j)| i == pos.0 || j == pos.1).map(|b| b.then(|| classes!("bg-teal-50", "dark:bg-teal-800")));
 
     let focus = {
-        let (focused, pos) = (focused.clone(), pos.clone());
-        Callback::from(move |_| focused.set(Some(pos.clone())))
+        let (focused, pos) = (focused.clone(), pos);
+        Callback::from(move |_| focused.set(Some(pos)))
     };
     let blur = {
         let focused = focused.clone();
@@ -342,3 +783,27 @@ pub fn table_cell(
         </div>
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        github::repository::GitHubToken,
+        host::instance::{HostKind, Instance},
+    };
+
+    #[test]
+    fn test_token_hint() {
+        let github = Repository::new("github", "hayas1", "tokei-toukei").unwrap();
+        assert!(token_hint(&github).unwrap().contains("GitHub token"));
+        assert_eq!(token_hint(&github.with_token(Some(GitHubToken::new("ghp_secret")))), None);
+
+        let origin = url::Url::parse("https://git.corp.example").unwrap();
+        let enterprise = Instance::new("corp", HostKind::GitHub, origin);
+        let repository = Repository::GitHub(GitHubRepository::enterprise(&enterprise, "hayas1", "tokei-toukei"));
+        assert!(token_hint(&repository).unwrap().contains("instance"));
+        let gitlab = Repository::new("gitlab", "group", "project").unwrap();
+        assert!(token_hint(&gitlab).unwrap().contains("instance"));
+        assert_eq!(token_hint(&Repository::new("codeberg", "forgejo", "forgejo").unwrap()), None);
+    }
+}
@@ -0,0 +1,11 @@
+#[derive(Debug, thiserror::Error)]
+pub enum CacheError {
+    #[error("IndexedDB is unavailable")]
+    Unavailable,
+
+    #[error("IndexedDB error: {0}")]
+    IndexedDb(String),
+
+    #[error("the cache directory is already set to {0}")]
+    DirectoryAlreadySet(String),
+}
@@ -0,0 +1,11 @@
+#[derive(Debug, thiserror::Error)]
+pub enum CliError {
+    #[error("invalid target `{0}`, expected `{{host}}:{{owner}}/{{repo}}` or the url of the repository")]
+    InvalidTarget(String),
+
+    #[error("invalid policy: {0}")]
+    InvalidPolicy(String),
+
+    #[error("invalid instance `{0}`, expected `{{kind}}={{origin}}` such as `gitlab=https://git.example.com`")]
+    InvalidInstance(String),
+}
@@ -14,4 +14,13 @@ pub enum Unreachable {
 pub enum ConvertError {
     #[error("Option length should be lower than one")]
     OptionLengthShouldBeLowerThanOne,
+
+    #[error("Unknown submodule mode: {0}")]
+    UnknownSubmoduleMode(String),
+
+    #[error("Unknown symlink mode: {0}")]
+    UnknownSymlinkMode(String),
+
+    #[error("Unknown ignore files mode: {0}")]
+    UnknownIgnoreFilesMode(String),
 }
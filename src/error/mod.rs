@@ -1,9 +1,20 @@
+pub mod cache;
+pub mod cli;
 pub mod convert;
 pub mod render;
 pub mod repository;
+pub mod server;
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 #[derive(Debug, thiserror::Error)]
 #[error(transparent)]
 pub struct Error(#[from] anyhow::Error);
+impl Error {
+    pub fn downcast_ref<E>(&self) -> Option<&E>
+    where
+        E: std::fmt::Display + std::fmt::Debug + Send + Sync + 'static,
+    {
+        self.0.downcast_ref()
+    }
+}
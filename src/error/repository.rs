@@ -3,6 +3,9 @@ pub enum UrlParseError {
     #[error("invalid host")]
     InvalidHost,
 
+    #[error("unsupported host: {0}")]
+    UnsupportedHost(String),
+
     #[error("unspecified owner and repository")]
     Unspecified,
 
@@ -13,8 +16,46 @@ pub enum UrlParseError {
     UnspecifiedRepository,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum InstanceError {
+    #[error("invalid instance name: {0}")]
+    InvalidName(String),
+
+    #[error("instance `{0}` is already registered")]
+    Duplicated(String),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AbortError {
+    #[error("the walk is aborted")]
+    Aborted,
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Unreachable {
     #[error("unimplemented string")]
     UnimplementedString,
 }
+
+#[derive(Debug, Clone, Eq, PartialEq, thiserror::Error)]
+pub enum RateLimitError {
+    /// `reset` is the epoch seconds of `X-RateLimit-Reset`
+    #[error("rate limit exceeded, resets at {reset}")]
+    Exceeded { reset: u64 },
+
+    /// `seconds` is the value of `Retry-After`
+    #[error("secondary rate limit exceeded, retry after {seconds} seconds")]
+    RetryAfter { seconds: u64 },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ResponseError {
+    #[error("unexpected status {status} from {url}")]
+    UnexpectedStatus { status: u16, url: String },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GraphQLError {
+    #[error("GraphQL query failed: {0}")]
+    Failed(String),
+}
@@ -0,0 +1,11 @@
+#[derive(Debug, thiserror::Error)]
+pub enum ServerError {
+    #[error("no route for `{0}`, expected `/api/{{host}}/{{owner}}/{{repo}}`")]
+    NotFound(String),
+
+    #[error("method `{0}` is not allowed")]
+    MethodNotAllowed(String),
+
+    #[error("bad request: {0}")]
+    BadRequest(String),
+}
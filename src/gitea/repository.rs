@@ -0,0 +1,196 @@
+use std::path::Path;
+
+use url::Url;
+
+use crate::{
+    error::{
+        repository::{Unreachable, UrlParseError},
+        Result,
+    },
+    github::{
+        models::{CommitsModel, TreesModel},
+        rate_limit::RateLimit,
+    },
+    host::{
+        control::WalkControl,
+        fetch::{Fetch, Fetcher, HttpRequest, HttpResponse},
+        filter::PathFilter,
+        instance::{HostKind, Instance},
+        Commit, RepositoryHost,
+    },
+};
+
+use super::models::RepoModel;
+
+/// Gitea compatible repository, such as Forgejo and Codeberg
+/// `name` is the name of the instance, which is used as the `:host` segment of the route
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GiteaRepository {
+    pub name: String,
+    pub origin: Url,
+    pub owner: String,
+    pub repo: String,
+    pub control: Option<WalkControl>,
+    pub fetcher: Fetcher,
+}
+
+impl GiteaRepository {
+    /// the git trees API of Gitea is paginated even if it is recursive
+    pub const PER_PAGE: usize = 1000;
+
+    pub fn new(instance: &Instance, owner: &str, repo: &str) -> Self {
+        let (name, origin) = (instance.name.clone(), instance.origin.clone());
+        Self {
+            name,
+            origin,
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            control: None,
+            fetcher: Fetcher::default(),
+        }
+    }
+
+    /// parse the url such as `https://codeberg.org/{owner}/{repo}/src/branch/main` of the instance
+    pub fn from_instance_url(instance: &Instance, url: &Url) -> Result<Self> {
+        let mut path_segments = instance.relative_segments(url)?.into_iter();
+        let owner = path_segments.next().ok_or_else(|| anyhow::anyhow!(UrlParseError::UnspecifiedOwner))?;
+        let repo = path_segments.next().ok_or_else(|| anyhow::anyhow!(UrlParseError::UnspecifiedRepository))?;
+        Ok(Self::new(instance, &owner, repo.trim_end_matches(".git")))
+    }
+
+    pub fn with_control(self, control: Option<WalkControl>) -> Self {
+        Self { control, ..self }
+    }
+
+    pub fn with_fetcher(self, fetcher: Fetcher) -> Self {
+        Self { fetcher, ..self }
+    }
+
+    fn endpoint<'a, I: IntoIterator<Item = &'a str>>(&self, path: I) -> Result<Url> {
+        let mut url = self.origin.clone();
+        url.path_segments_mut().map_err(|_| anyhow::anyhow!(UrlParseError::Unspecified))?.pop_if_empty().extend(path);
+        Ok(url)
+    }
+
+    /// `path` segments are appended to `/api/v1/repos/{owner}/{repo}`
+    pub fn api_endpoint<'a, I: IntoIterator<Item = &'a str>>(&self, path: I) -> Result<Url> {
+        let mut url = self.endpoint(["api", "v1", "repos", &self.owner, &self.repo])?;
+        url.path_segments_mut().map_err(|_| anyhow::anyhow!(UrlParseError::Unspecified))?.extend(path);
+        Ok(url)
+    }
+
+    pub async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        RateLimit::check(self.fetcher.fetch(request, self.control).await?)
+    }
+
+    /// `reference` can be a branch, a tag, or a commit SHA
+    pub async fn commits(&self, reference: &str) -> Result<CommitsModel> {
+        let request = HttpRequest::get(self.api_endpoint(["git", "commits", reference])?);
+        self.send(request).await?.json()
+    }
+
+    pub async fn trees(&self, sha: &str, page: usize) -> Result<TreesModel> {
+        let request = HttpRequest::get(self.api_endpoint(["git", "trees", sha])?).query([
+            ("recursive", true.to_string()),
+            ("per_page", Self::PER_PAGE.to_string()),
+            ("page", page.to_string()),
+        ]);
+        self.send(request).await?.json()
+    }
+
+    pub async fn raw<A: AsRef<Path>>(&self, sha: &str, path: A) -> Result<Vec<u8>> {
+        let path = path.as_ref().to_str().ok_or_else(|| anyhow::anyhow!(Unreachable::UnimplementedString))?;
+        let mut url = self.api_endpoint(["raw"])?;
+        url.path_segments_mut().map_err(|_| anyhow::anyhow!(UrlParseError::Unspecified))?.extend(path.split('/'));
+        let request = HttpRequest::get(url).query([("ref", sha)]);
+        Ok(self.send(request).await?.binary())
+    }
+}
+
+impl RepositoryHost for GiteaRepository {
+    fn host(&self) -> String {
+        self.name.clone()
+    }
+
+    fn owner(&self) -> String {
+        self.owner.clone()
+    }
+
+    fn repo(&self) -> String {
+        self.repo.clone()
+    }
+
+    /// only the builtin instances such as Codeberg are recognized, others should be registered as instances
+    fn from_url(url: &Url) -> Result<Self> {
+        let instances = Instance::builtin().into_iter().filter(|instance| instance.kind == HostKind::Gitea);
+        match instances.into_iter().find(|instance| instance.contains(url)) {
+            Some(instance) => Self::from_instance_url(&instance, url),
+            None => Err(anyhow::anyhow!(UrlParseError::InvalidHost))?,
+        }
+    }
+
+    fn to_url(&self) -> Result<Url> {
+        self.endpoint([&self.owner[..], &self.repo[..]])
+    }
+
+    fn tree_url(&self, sha: &str) -> Result<Url> {
+        self.endpoint([&self.owner[..], &self.repo[..], "src", "commit", sha])
+    }
+
+    async fn default_branch(&self) -> Result<String> {
+        let request = HttpRequest::get(self.api_endpoint([])?);
+        let RepoModel { default_branch } = self.send(request).await?.json()?;
+        Ok(default_branch.unwrap_or("main".to_string()))
+    }
+
+    async fn commit(&self, reference: &str) -> Result<Commit> {
+        let CommitsModel { sha, commit, .. } = self.commits(reference).await?;
+        Ok(Commit { reference: reference.to_string(), sha, message: commit.message })
+    }
+
+    /// `truncated` of the paginated tree means that the next page exists
+    async fn tree(&self, sha: &str, _filter: &PathFilter) -> Result<TreesModel> {
+        let TreesModel { sha, url, mut tree, mut truncated } = self.trees(sha, 1).await?;
+        let mut page = 1;
+        while truncated {
+            page += 1;
+            let next = self.trees(&sha, page).await?;
+            truncated = next.truncated && !next.tree.is_empty();
+            tree.extend(next.tree);
+        }
+        Ok(TreesModel { sha, url, tree, truncated })
+    }
+
+    async fn raw(&self, sha: &str, path: &Path) -> Result<Vec<u8>> {
+        GiteaRepository::raw(self, sha, path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repo_url() {
+        let url = Url::parse("https://codeberg.org/forgejo/forgejo/src/branch/forgejo").unwrap();
+        let repo = GiteaRepository::from_url(&url).unwrap();
+        assert_eq!((repo.host(), repo.owner(), repo.repo()), ("codeberg".into(), "forgejo".into(), "forgejo".into()));
+        assert_eq!(repo.to_url().unwrap().as_str(), "https://codeberg.org/forgejo/forgejo");
+
+        let url = Url::parse("https://github.com/hayas1/tokei-toukei").unwrap();
+        assert!(GiteaRepository::from_url(&url).is_err());
+    }
+
+    #[test]
+    fn test_instance_with_subpath() {
+        let origin = Url::parse("https://forge.example.com/gitea/").unwrap();
+        let instance = Instance::new("forge", HostKind::Gitea, origin);
+        let url = Url::parse("https://forge.example.com/gitea/owner/repo").unwrap();
+        let repo = GiteaRepository::from_instance_url(&instance, &url).unwrap();
+        assert_eq!(repo.to_url().unwrap().as_str(), "https://forge.example.com/gitea/owner/repo");
+        assert_eq!(
+            repo.api_endpoint(["git", "trees", "main"]).unwrap().as_str(),
+            "https://forge.example.com/gitea/api/v1/repos/owner/repo/git/trees/main"
+        );
+    }
+}
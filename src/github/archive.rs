@@ -0,0 +1,86 @@
+use std::{
+    collections::HashSet,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use flate2::read::GzDecoder;
+use tar::{Archive, EntryType};
+
+use crate::error::Result;
+
+use super::blob::GitHubBlob;
+
+/// decode the tarball (`.tar.gz`) of the repository, which is called codeball by GitHub
+pub struct TarballDecoder<'a> {
+    bytes: &'a [u8],
+}
+impl<'a> TarballDecoder<'a> {
+    /// total size of the tree that the tarball is preferred to the per-file requests
+    pub const MAX_TREE_SIZE: u64 = 64 * 1024 * 1024;
+    /// the per-file requests are cheaper than the tarball for a few files
+    pub const MIN_FILES: usize = 64;
+
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn suitable(tree_size: u64, files: usize) -> bool {
+        tree_size <= Self::MAX_TREE_SIZE && files >= Self::MIN_FILES
+    }
+
+    /// decode the regular files in `paths`, other entries are skipped
+    pub fn decode(&self, paths: &HashSet<PathBuf>) -> Result<Vec<GitHubBlob>> {
+        let mut archive = Archive::new(GzDecoder::new(self.bytes));
+        let mut blobs = Vec::new();
+        for entry in archive.entries().map_err(anyhow::Error::from)? {
+            let mut entry = entry.map_err(anyhow::Error::from)?;
+            if entry.header().entry_type() != EntryType::Regular {
+                continue;
+            }
+            let path = Self::strip_root(&entry.path().map_err(anyhow::Error::from)?);
+            if !paths.contains(&path) {
+                continue;
+            }
+            let mut content = Vec::new();
+            entry.read_to_end(&mut content).map_err(anyhow::Error::from)?;
+            blobs.push(GitHubBlob::new(path, content));
+        }
+        Ok(blobs)
+    }
+
+    /// entries of the tarball are placed under `{owner}-{repo}-{short sha}/`
+    fn strip_root(path: &Path) -> PathBuf {
+        path.components().skip(1).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::{write::GzEncoder, Compression};
+    use tar::{Builder, Header};
+
+    use super::*;
+
+    fn tarball(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (path, content) in files {
+            let mut header = Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, format!("hayas1-tokei-toukei-1c2569b/{path}"), content.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn test_decode_tarball() {
+        let bytes = tarball(&[("src/main.rs", "fn main() {}\n"), ("README.md", "# tokei-toukei\n")]);
+        let paths = HashSet::from_iter([PathBuf::from("src/main.rs")]);
+        let blobs = TarballDecoder::new(&bytes).decode(&paths).unwrap();
+        assert_eq!(blobs, vec![GitHubBlob::new(PathBuf::from("src/main.rs"), b"fn main() {}\n".to_vec())]);
+    }
+}
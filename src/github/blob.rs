@@ -1,19 +1,33 @@
 use base64::prelude::*;
 use std::path::PathBuf;
 
-use crate::error::Result;
+use crate::{error::Result, host::content::Content};
 
 use super::models::{BlobsModel, EncodingType};
 
+/// `content` is the raw bytes, which may be binary or encoded in other than UTF-8
+/// `binary` is true if the host tells that the blob is binary, then its content is not downloaded and is empty
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct GitHubBlob {
     pub path: PathBuf,
-    pub content: String,
+    pub content: Vec<u8>,
+    pub binary: bool,
 }
 
 impl GitHubBlob {
-    pub fn new(path: PathBuf, content: String) -> Self {
-        Self { path, content }
+    pub fn new(path: PathBuf, content: Vec<u8>) -> Self {
+        Self { path, content, binary: false }
+    }
+
+    pub fn binary(path: PathBuf) -> Self {
+        Self { path, content: Vec::new(), binary: true }
+    }
+
+    pub fn decode(&self) -> Content {
+        match self.binary {
+            true => Content::Binary,
+            false => Content::decode(&self.content),
+        }
     }
 
     pub fn from_model(path: PathBuf, model: BlobsModel) -> Result<Self> {
@@ -30,16 +44,15 @@ impl<'a> BlobsModelDecoder<'a> {
         Self { model }
     }
 
-    pub fn decode(&self) -> Result<String> {
+    pub fn decode(&self) -> Result<Vec<u8>> {
         match self.model.encoding {
             EncodingType::Base64 => self.decode_base64(),
         }
     }
 
-    pub fn decode_base64(&self) -> Result<String> {
+    pub fn decode_base64(&self) -> Result<Vec<u8>> {
         let mut encoded = self.model.content.as_bytes().to_owned();
         encoded.retain(|b| !b.is_ascii_whitespace());
-        let decoded = BASE64_STANDARD.decode(encoded).map_err(anyhow::Error::from)?;
-        Ok(String::from_utf8_lossy(&decoded).into_owned())
+        Ok(BASE64_STANDARD.decode(encoded).map_err(anyhow::Error::from)?)
     }
 }
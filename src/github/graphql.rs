@@ -0,0 +1,202 @@
+use std::path::PathBuf;
+
+use serde_json::json;
+
+use crate::error::{
+    repository::{GraphQLError, RateLimitError},
+    Result,
+};
+
+use super::{
+    blob::GitHubBlob,
+    models::{BlobObjectModel, BlobsQueryModel, GraphQLModel, SubtreeModel},
+    rate_limit::RateLimit,
+};
+
+/// files of a chunk are fetched by a single GraphQL query, each of them is aliased by its index such as `f0`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BlobsQuery {
+    pub blobs: Vec<SubtreeModel>,
+}
+
+impl BlobsQuery {
+    /// a query that requests up to 100 nodes costs 1 point of the rate limit
+    pub const MAX_FILES: usize = 100;
+    /// the texts of all files of the chunk are in a response, so it is kept small
+    pub const MAX_BYTES: u64 = 1 << 21;
+    /// the type of the error that GitHub returns when the rate limit of the GraphQL API is exceeded
+    pub const RATE_LIMITED: &'static str = "RATE_LIMITED";
+
+    /// split the blobs into chunks, a blob that is larger than `MAX_BYTES` is in a chunk by itself
+    pub fn chunks(blobs: Vec<SubtreeModel>) -> Vec<Self> {
+        let (mut chunks, mut chunk, mut bytes) = (Vec::new(), Vec::new(), 0);
+        for blob in blobs {
+            let size = blob.size.unwrap_or_default();
+            if !chunk.is_empty() && (chunk.len() >= Self::MAX_FILES || bytes + size > Self::MAX_BYTES) {
+                chunks.push(Self { blobs: std::mem::take(&mut chunk) });
+                bytes = 0;
+            }
+            bytes += size;
+            chunk.push(blob);
+        }
+        if !chunk.is_empty() {
+            chunks.push(Self { blobs: chunk });
+        }
+        chunks
+    }
+
+    fn alias(index: usize) -> String {
+        format!("f{index}")
+    }
+
+    /// the expression is quoted as a JSON string, which is also a valid GraphQL string
+    pub fn query(&self, sha: &str) -> String {
+        let objects: String = self
+            .blobs
+            .iter()
+            .enumerate()
+            .map(|(index, blob)| {
+                let expression = json!(format!("{sha}:{}", blob.path));
+                let alias = Self::alias(index);
+                format!("    {alias}: object(expression: {expression}) {{ ... on Blob {{ text isBinary isTruncated byteSize }} }}\n")
+            })
+            .collect();
+        format!("query($owner: String!, $repo: String!) {{\n  repository(owner: $owner, name: $repo) {{\n{objects}  }}\n}}\n")
+    }
+
+    pub fn body(&self, owner: &str, repo: &str, sha: &str) -> serde_json::Value {
+        json!({ "query": self.query(sha), "variables": { "owner": owner, "repo": repo } })
+    }
+
+    /// the errors are fatal only if `data` is missing, because a partial error such as a missing path nulls the object
+    pub fn data(response: GraphQLModel<BlobsQueryModel>, rate_limit: &RateLimit) -> Result<BlobsQueryModel> {
+        let GraphQLModel { data, errors } = response;
+        if errors.iter().any(|error| error.error_type.as_deref() == Some(Self::RATE_LIMITED)) {
+            let err = match rate_limit.reset {
+                Some(reset) => RateLimitError::Exceeded { reset },
+                None => RateLimitError::RetryAfter { seconds: 60 },
+            };
+            Err(anyhow::anyhow!(err))?
+        }
+        match data {
+            Some(data) => Ok(data),
+            None => {
+                let messages: Vec<_> = errors.into_iter().map(|error| error.message).collect();
+                Err(anyhow::anyhow!(GraphQLError::Failed(messages.join(", "))))?
+            }
+        }
+    }
+
+    /// the text is decoded by GitHub with the replacement characters, so it is taken only if it is the same bytes as
+    /// the blob, the other encodings such as Shift_JIS are detected from the bytes like the other requests
+    pub fn is_lossless(object: &BlobObjectModel) -> bool {
+        match object {
+            BlobObjectModel { text: Some(text), is_truncated: Some(false), byte_size: Some(byte_size), .. } => {
+                text.len() as u64 == *byte_size && !text.contains(char::REPLACEMENT_CHARACTER)
+            }
+            _ => false,
+        }
+    }
+
+    /// binary blobs are returned without their content, and the blobs whose text is not returned as it is, such as
+    /// truncated large files or the files in the other encodings, are left to be fetched by the other requests
+    pub fn decode(self, data: BlobsQueryModel) -> (Vec<GitHubBlob>, Vec<SubtreeModel>) {
+        let mut objects = data.repository.unwrap_or_default();
+        let (mut blobs, mut remaining) = (Vec::new(), Vec::new());
+        for (index, subtree) in self.blobs.into_iter().enumerate() {
+            match objects.remove(&Self::alias(index)).flatten() {
+                Some(BlobObjectModel { is_binary: Some(true), .. }) => {
+                    blobs.push(GitHubBlob::binary(PathBuf::from(subtree.path)))
+                }
+                Some(object) if Self::is_lossless(&object) => {
+                    let text = object.text.unwrap_or_default();
+                    blobs.push(GitHubBlob::new(PathBuf::from(subtree.path), text.into_bytes()))
+                }
+                _ => remaining.push(subtree),
+            }
+        }
+        (blobs, remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subtree(path: &str, size: u64) -> SubtreeModel {
+        serde_json::from_value(json!({ "path": path, "mode": "100644", "type": "blob", "sha": path, "size": size }))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_chunks() {
+        let blobs: Vec<_> = (0..250).map(|i| subtree(&format!("src/{i}.rs"), 10)).collect();
+        let chunks = BlobsQuery::chunks(blobs);
+        assert_eq!(chunks.iter().map(|chunk| chunk.blobs.len()).collect::<Vec<_>>(), vec![100, 100, 50]);
+
+        let blobs = vec![subtree("a.rs", 10), subtree("large.json", BlobsQuery::MAX_BYTES + 1), subtree("b.rs", 10)];
+        let chunks = BlobsQuery::chunks(blobs);
+        assert_eq!(chunks.iter().map(|chunk| chunk.blobs.len()).collect::<Vec<_>>(), vec![1, 1, 1]);
+        assert!(BlobsQuery::chunks(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_query() {
+        let query = BlobsQuery { blobs: vec![subtree("src/main.rs", 10), subtree(r#"say "hi".txt"#, 10)] };
+        let body = query.body("hayas1", "tokei-toukei", "v1");
+        assert_eq!(body["variables"], json!({ "owner": "hayas1", "repo": "tokei-toukei" }));
+        let text = body["query"].as_str().unwrap();
+        assert!(text.contains(r#"f0: object(expression: "v1:src/main.rs")"#));
+        assert!(text.contains(r#"f1: object(expression: "v1:say \"hi\".txt")"#));
+        assert_eq!(text.matches("... on Blob { text isBinary isTruncated byteSize }").count(), 2);
+    }
+
+    #[test]
+    fn test_decode() {
+        let lossy = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+        let query = BlobsQuery {
+            blobs: vec![
+                subtree("main.rs", 13),
+                subtree("logo.png", 8),
+                subtree("large.rs", 1 << 20),
+                subtree("gone.rs", 1),
+                subtree("sjis.txt", 10),
+                subtree("latin1.txt", 5),
+            ],
+        };
+        let response = json!({
+            "data": { "repository": {
+                "f0": { "text": "fn main() {}\n", "isBinary": false, "isTruncated": false, "byteSize": 13 },
+                "f1": { "text": null, "isBinary": true, "isTruncated": false, "byteSize": 8 },
+                "f2": { "text": "fn", "isBinary": false, "isTruncated": true, "byteSize": 1048576 },
+                "f3": null,
+                // the texts of the blobs in Shift_JIS and windows-1252, which GitHub decodes with the replacement
+                "f4": { "text": lossy(b"\x82\xb1\x82\xf1\x82\xc9\x82\xbf\x82\xcd"), "isBinary": false, "isTruncated": false, "byteSize": 10 },
+                "f5": { "text": lossy(b"caf\xe9\n"), "isBinary": false, "isTruncated": false, "byteSize": 5 },
+            } },
+            "errors": [{ "type": "NOT_FOUND", "message": "Could not resolve to a node" }],
+        });
+        let data = BlobsQuery::data(serde_json::from_value(response).unwrap(), &RateLimit::default()).unwrap();
+        let (blobs, remaining) = query.decode(data);
+        assert_eq!(
+            blobs,
+            vec![GitHubBlob::new("main.rs".into(), b"fn main() {}\n".to_vec()), GitHubBlob::binary("logo.png".into())]
+        );
+        assert_eq!(
+            remaining.into_iter().map(|subtree| subtree.path).collect::<Vec<_>>(),
+            vec!["large.rs", "gone.rs", "sjis.txt", "latin1.txt"]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let rate_limited = json!({ "errors": [{ "type": "RATE_LIMITED", "message": "API rate limit exceeded" }] });
+        let rate_limit = RateLimit { reset: Some(1717171717), ..Default::default() };
+        let err = BlobsQuery::data(serde_json::from_value(rate_limited).unwrap(), &rate_limit).unwrap_err();
+        assert_eq!(err.downcast_ref::<RateLimitError>(), Some(&RateLimitError::Exceeded { reset: 1717171717 }));
+
+        let failed = json!({ "data": null, "errors": [{ "message": "Something went wrong" }] });
+        let err = BlobsQuery::data(serde_json::from_value(failed).unwrap(), &RateLimit::default()).unwrap_err();
+        assert!(err.downcast_ref::<GraphQLError>().is_some());
+    }
+}
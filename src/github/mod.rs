@@ -1,4 +1,6 @@
+pub mod archive;
 pub mod blob;
+pub mod graphql;
 pub mod models;
+pub mod rate_limit;
 pub mod repository;
-pub mod statistics;
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use url::Url;
 
@@ -22,13 +24,62 @@ pub struct SubtreeModel {
     pub url: Option<Url>,
 }
 
+impl SubtreeModel {
+    pub fn file_mode(&self) -> FileMode {
+        FileMode::from(&self.mode[..])
+    }
+}
+
+/// symlinks are also `blob`, so they are distinguished by the mode
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ContentsType {
     Tree,
     Blob,
     Commit,
-    // TODO: symlink ?
+}
+
+/// mode of the entry of the git tree
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum FileMode {
+    Regular,
+    Executable,
+    Symlink,
+    Directory,
+    Gitlink,
+    Unknown,
+}
+impl From<&str> for FileMode {
+    fn from(mode: &str) -> Self {
+        match mode {
+            "100644" | "100664" => Self::Regular,
+            "100755" => Self::Executable,
+            "120000" => Self::Symlink,
+            "040000" | "40000" => Self::Directory,
+            "160000" => Self::Gitlink,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CommitsModel {
+    pub sha: String,
+    pub url: Url,
+    pub html_url: Url,
+    pub commit: GitCommitModel,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GitCommitModel {
+    pub message: String,
+    pub tree: GitTreeModel,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GitTreeModel {
+    pub sha: String,
+    pub url: Url,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -46,3 +97,34 @@ pub struct BlobsModel {
 pub enum EncodingType {
     Base64,
 }
+
+/// response of the GraphQL API, `data` may be partial when `errors` are also returned
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GraphQLModel<T> {
+    pub data: Option<T>,
+    #[serde(default)]
+    pub errors: Vec<GraphQLErrorModel>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GraphQLErrorModel {
+    #[serde(rename = "type")]
+    pub error_type: Option<String>,
+    pub message: String,
+}
+
+/// the objects of the repository are keyed by their aliases, and they are null if the path does not exist
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BlobsQueryModel {
+    pub repository: Option<HashMap<String, Option<BlobObjectModel>>>,
+}
+
+/// all fields are missing if the object is not a blob, and `text` is null if the blob is binary
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobObjectModel {
+    pub text: Option<String>,
+    pub is_binary: Option<bool>,
+    pub is_truncated: Option<bool>,
+    pub byte_size: Option<u64>,
+}
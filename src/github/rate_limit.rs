@@ -0,0 +1,84 @@
+use crate::{
+    error::{
+        repository::{RateLimitError, ResponseError},
+        Result,
+    },
+    host::fetch::HttpResponse,
+};
+
+/// rate limit headers of the response
+/// https://docs.github.com/en/rest/using-the-rest-api/rate-limits-for-the-rest-api
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct RateLimit {
+    pub remaining: Option<u64>,
+    pub reset: Option<u64>,
+    pub retry_after: Option<u64>,
+}
+impl RateLimit {
+    pub const HEADER_REMAINING: &'static str = "x-ratelimit-remaining";
+    pub const HEADER_RESET: &'static str = "x-ratelimit-reset";
+    pub const HEADER_RETRY_AFTER: &'static str = "retry-after";
+
+    pub fn from_headers<F: Fn(&str) -> Option<String>>(get: F) -> Self {
+        let parse = |name| get(name).and_then(|value| value.trim().parse().ok());
+        Self {
+            remaining: parse(Self::HEADER_REMAINING),
+            reset: parse(Self::HEADER_RESET),
+            retry_after: parse(Self::HEADER_RETRY_AFTER),
+        }
+    }
+
+    /// GitHub returns 403 or 429 when the rate limit is exceeded
+    pub fn exceeded(&self, status: u16) -> Option<RateLimitError> {
+        if !matches!(status, 403 | 429) {
+            return None;
+        }
+        match (self.retry_after, self.remaining, self.reset) {
+            (Some(seconds), _, _) => Some(RateLimitError::RetryAfter { seconds }),
+            (None, Some(0), Some(reset)) => Some(RateLimitError::Exceeded { reset }),
+            (None, Some(0), None) => Some(RateLimitError::RetryAfter { seconds: 60 }),
+            _ => None,
+        }
+    }
+
+    /// check the status and the rate limit of every response, `304 Not Modified` of the conditional request is passed
+    pub fn check(response: HttpResponse) -> Result<HttpResponse> {
+        let rate_limit = Self::from_headers(|name| response.header(name));
+        if let Some(err) = rate_limit.exceeded(response.status) {
+            Err(anyhow::anyhow!(err))?
+        } else if !response.ok() && response.status != 304 {
+            let (status, url) = (response.status, response.url.clone());
+            Err(anyhow::anyhow!(ResponseError::UnexpectedStatus { status, url }))?
+        }
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn rate_limit(headers: &[(&str, &str)]) -> RateLimit {
+        let headers: HashMap<_, _> = headers.iter().cloned().collect();
+        RateLimit::from_headers(|name| headers.get(name).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn test_rate_limit_exceeded() {
+        let exhausted = rate_limit(&[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "1717171717")]);
+        assert_eq!(exhausted.exceeded(403), Some(RateLimitError::Exceeded { reset: 1717171717 }));
+        assert_eq!(exhausted.exceeded(200), None);
+
+        let secondary = rate_limit(&[("x-ratelimit-remaining", "42"), ("retry-after", "30")]);
+        assert_eq!(secondary.exceeded(429), Some(RateLimitError::RetryAfter { seconds: 30 }));
+    }
+
+    #[test]
+    fn test_forbidden_is_not_rate_limit() {
+        let remaining = rate_limit(&[("x-ratelimit-remaining", "59"), ("x-ratelimit-reset", "1717171717")]);
+        assert_eq!(remaining.exceeded(403), None);
+        assert_eq!(rate_limit(&[]).exceeded(403), None);
+    }
+}
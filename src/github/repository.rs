@@ -1,24 +1,105 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashSet,
+    fmt::Debug,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+};
 
-use futures::{stream, Stream, StreamExt, TryStreamExt};
-use gloo::net::http::Request;
+use futures::{
+    stream::{self, LocalBoxStream},
+    Stream, StreamExt, TryStreamExt,
+};
 use octocrab::models;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use url::Url;
 
 use crate::{
     error::{
-        repository::{Unreachable, UrlParseError},
+        repository::{RateLimitError, Unreachable, UrlParseError},
         Result,
     },
-    github::models::{ContentsType, SubtreeModel, TreesModel},
+    github::models::{BlobsModel, CommitsModel, ContentsType, SubtreeModel, TreesModel},
+    host::{
+        self,
+        cache::{Cache, CachedResponse},
+        control::WalkControl,
+        fetch::{Fetch, Fetcher, HttpRequest, HttpResponse},
+        filter::PathFilter,
+        instance::Instance,
+        Commit, RepositoryHost,
+    },
 };
 
-use super::{blob::GitHubBlob, statistics::Statistics};
+use super::{
+    archive::TarballDecoder,
+    blob::{BlobsModelDecoder, GitHubBlob},
+    graphql::BlobsQuery,
+    rate_limit::RateLimit,
+};
 
+/// `name` is used as the `:host` segment of the route, it is `github` for github.com
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct GitHubRepository {
+    pub name: String,
+    pub origins: GitHubOrigins,
     pub owner: String,
     pub repo: String,
+    pub token: Option<GitHubToken>,
+    /// the API responses are cached with their ETags only if it is enabled
+    pub cache: bool,
+    pub control: Option<WalkControl>,
+    pub fetcher: Fetcher,
+}
+
+/// origins of the web, the API, and the raw files, which differ between github.com and GitHub Enterprise Server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitHubOrigins {
+    pub origin: Url,
+    pub api: Url,
+    pub raw: Url,
+}
+impl Default for GitHubOrigins {
+    fn default() -> Self {
+        let parse = |origin| Url::parse(origin).expect("origins of github.com should be valid url");
+        Self {
+            origin: parse(GitHubRepository::ORIGIN),
+            api: parse(GitHubRepository::API_ORIGIN),
+            raw: parse(GitHubRepository::RAW_ORIGIN),
+        }
+    }
+}
+impl GitHubOrigins {
+    /// GitHub Enterprise Server serves the API under `/api/v3` and the raw files under `/raw`
+    pub fn enterprise(origin: Url) -> Self {
+        let api = GitHubRepository::endpoint(&origin, "/api/v3");
+        let raw = GitHubRepository::endpoint(&origin, "/raw");
+        Self { origin, api, raw }
+    }
+}
+
+/// personal access token, which is not shown in the debug output
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitHubToken(String);
+impl Debug for GitHubToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("GitHubToken").field(&"********").finish()
+    }
+}
+impl GitHubToken {
+    pub fn new(token: &str) -> Self {
+        Self(token.to_string())
+    }
+
+    pub fn secret(&self) -> &str {
+        &self.0
+    }
+
+    /// hash of the token, so that the responses for a token are not served for another one without the secret
+    pub fn identity(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.0.hash(&mut hasher);
+        format!("{:016x}", hasher.finish())
+    }
 }
 
 impl GitHubRepository {
@@ -27,89 +108,396 @@ impl GitHubRepository {
     pub const RAW_ORIGIN: &'static str = "https://raw.githubusercontent.com";
 
     pub fn new(owner: &str, repo: &str) -> Self {
-        Self { owner: owner.to_string(), repo: repo.to_string() }
+        let (name, origins) = ("github".to_string(), GitHubOrigins::default());
+        Self {
+            name,
+            origins,
+            owner: owner.to_string(),
+            repo: repo.to_string(),
+            token: None,
+            cache: true,
+            control: None,
+            fetcher: Fetcher::default(),
+        }
     }
 
-    pub fn host(&self) -> String {
-        "github".to_string()
+    /// repository of GitHub Enterprise Server, the token of the instance is used instead of the one of github.com
+    pub fn enterprise(instance: &Instance, owner: &str, repo: &str) -> Self {
+        let origins = GitHubOrigins::enterprise(instance.origin.clone());
+        let repository = Self { name: instance.name.clone(), origins, ..Self::new(owner, repo) };
+        repository.with_token(instance.token.clone())
     }
 
-    pub fn from_url(url: &Url) -> Result<Self> {
-        if url.origin().unicode_serialization() != Self::ORIGIN {
-            Err(anyhow::anyhow!(UrlParseError::InvalidHost))?
-        }
-        let mut path_segments = url.path_segments().ok_or_else(|| anyhow::anyhow!(UrlParseError::Unspecified))?;
+    /// parse the url such as `https://git.corp.example/{owner}/{repo}/tree/main` of the instance
+    pub fn from_instance_url(instance: &Instance, url: &Url) -> Result<Self> {
+        let mut path_segments = instance.relative_segments(url)?.into_iter();
         let owner = path_segments.next().ok_or_else(|| anyhow::anyhow!(UrlParseError::UnspecifiedOwner))?;
         let repo = path_segments.next().ok_or_else(|| anyhow::anyhow!(UrlParseError::UnspecifiedRepository))?;
-        // TODO rest path
-        Ok(Self::new(owner, repo))
+        Ok(Self::enterprise(instance, &owner, repo.trim_end_matches(".git")))
     }
 
-    pub fn to_url(&self) -> Result<Url> {
-        let mut url = Url::parse(Self::ORIGIN).map_err(anyhow::Error::from)?;
-        url.set_path(&[&self.owner[..], &self.repo[..]].join("/"));
-        Ok(url)
+    pub fn with_token(self, token: Option<GitHubToken>) -> Self {
+        Self { token, ..self }
+    }
+
+    pub fn with_cache(self, cache: bool) -> Self {
+        Self { cache, ..self }
+    }
+
+    pub fn with_control(self, control: Option<WalkControl>) -> Self {
+        Self { control, ..self }
+    }
+
+    pub fn with_fetcher(self, fetcher: Fetcher) -> Self {
+        Self { fetcher, ..self }
+    }
+
+    pub fn is_github_com(&self) -> bool {
+        self.origins == GitHubOrigins::default()
+    }
+
+    /// `path` is appended to the path of `base`, such as `/api/v3` of GitHub Enterprise Server
+    fn endpoint(base: &Url, path: &str) -> Url {
+        let mut url = base.clone();
+        url.set_path(&format!("{}{path}", base.path().trim_end_matches('/')));
+        url
     }
 
     pub fn api_endpoint(&self, path: &str) -> Result<Url> {
-        let mut url = Url::parse(Self::API_ORIGIN).map_err(anyhow::Error::from)?;
-        url.set_path(path);
-        Ok(url)
+        Ok(Self::endpoint(&self.origins.api, path))
     }
 
     pub fn raw_endpoint(&self, path: &str) -> Result<Url> {
-        let mut url = Url::parse(Self::RAW_ORIGIN).map_err(anyhow::Error::from)?;
-        url.set_path(path);
-        Ok(url)
+        Ok(Self::endpoint(&self.origins.raw, path))
+    }
+
+    /// GitHub Enterprise Server serves the GraphQL API at `/api/graphql`, not under `/api/v3`
+    pub fn graphql_endpoint(&self) -> Result<Url> {
+        match self.is_github_com() {
+            true => Ok(Self::endpoint(&self.origins.api, "/graphql")),
+            false => Ok(Self::endpoint(&self.origins.origin, "/api/graphql")),
+        }
+    }
+
+    /// request to the API, with the token if it is given
+    pub fn api_request(&self, url: &Url) -> HttpRequest {
+        self.authorize(HttpRequest::get(url.clone()))
+    }
+
+    fn authorize(&self, request: HttpRequest) -> HttpRequest {
+        let request = request.header("Accept", "application/vnd.github+json");
+        match &self.token {
+            Some(token) => request.header("Authorization", &format!("Bearer {}", token.secret())),
+            None => request,
+        }
+    }
+
+    /// every response is checked whether the rate limit is exceeded, and the request is aborted with the walk
+    pub async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        RateLimit::check(self.fetcher.fetch(request, self.control).await?)
+    }
+
+    /// the GraphQL API requires the token, and its rate limit is reported in the errors of the response
+    pub async fn graphql_blobs(&self, sha: &str, query: BlobsQuery) -> Result<(Vec<GitHubBlob>, Vec<SubtreeModel>)> {
+        let Self { owner, repo, .. } = &self;
+        let body = query.body(owner, repo, sha);
+        let request = self.authorize(HttpRequest::post(self.graphql_endpoint()?, &body)?);
+        let response = self.send(request).await?;
+        let rate_limit = RateLimit::from_headers(|name| response.header(name));
+        let model = response.json()?;
+        Ok(query.decode(BlobsQuery::data(model, &rate_limit)?))
+    }
+
+    /// the responses are cached for each token, because the private repositories are visible only with the token
+    pub fn response_key(&self, url: &Url) -> String {
+        match &self.token {
+            Some(token) => format!("{url}#{}", token.identity()),
+            None => url.to_string(),
+        }
+    }
+
+    /// the response is cached with its ETag and revalidated by `If-None-Match`, the body of `304 Not Modified` is
+    /// served from the cache, and GitHub does not count it against the rate limit
+    pub async fn conditional_json<T: DeserializeOwned>(&self, url: &Url) -> Result<T> {
+        let cache = match self.cache {
+            true => Cache::open().await.ok(),
+            false => None,
+        };
+        let key = self.response_key(url);
+        let cached = match &cache {
+            Some(cache) => cache.get_response(&key).await.unwrap_or_default(),
+            None => None,
+        };
+        let request = match &cached {
+            Some(CachedResponse { etag, .. }) => self.api_request(url).header("If-None-Match", etag),
+            None => self.api_request(url),
+        };
+        let response = self.send(request).await?;
+        let body = match (response.status, cached) {
+            (304, Some(CachedResponse { body, .. })) => body,
+            _ => {
+                let body = response.text()?;
+                if let (Some(cache), Some(etag)) = (&cache, response.header("etag")) {
+                    let cached = CachedResponse { etag, body: body.clone() };
+                    if let Err(err) = cache.put_response(&key, &cached).await {
+                        host::warn(&err.to_string());
+                    }
+                }
+                body
+            }
+        };
+        Ok(serde_json::from_str(&body).map_err(anyhow::Error::from)?)
     }
 
     pub async fn trees(&self, sha: &str, recursive: bool) -> Result<TreesModel> {
-        let Self { owner, repo } = &self;
+        let Self { owner, repo, .. } = &self;
         let path = format!("/repos/{owner}/{repo}/git/trees/{sha}");
-        let request = Request::get(self.api_endpoint(&path)?.as_str()).query([("recursive", recursive.to_string())]);
-        Ok(request.send().await.map_err(anyhow::Error::from)?.json().await.map_err(anyhow::Error::from)?)
+        let mut url = self.api_endpoint(&path)?;
+        url.query_pairs_mut().append_pair("recursive", &recursive.to_string());
+        self.conditional_json(&url).await
+    }
+
+    /// list the entire tree, level by level with non-recursive requests if the recursive tree is truncated
+    /// `truncated` of the result is still true if some level of the tree is also truncated
+    pub async fn entire_trees(&self, sha: &str, filter: &PathFilter) -> Result<TreesModel> {
+        let trees = self.trees(sha, true).await?;
+        if !trees.truncated {
+            return Ok(trees);
+        }
+
+        let TreesModel { sha, url, tree: mut level, mut truncated } = self.trees(sha, false).await?;
+        let mut tree = Vec::new();
+        while !level.is_empty() {
+            let subtrees: Vec<_> = level
+                .iter()
+                .filter(|subtree| {
+                    subtree.contents_type == ContentsType::Tree && !filter.is_excluded(&subtree.path, true)
+                })
+                .cloned()
+                .collect();
+            tree.append(&mut level);
+
+            let children: Vec<_> = stream::iter(subtrees)
+                .map(|SubtreeModel { path, sha, .. }| async move {
+                    self.trees(&sha, false).await.map(|trees| (path, trees))
+                })
+                .buffered(8)
+                .try_collect()
+                .await?;
+            for (parent, TreesModel { tree: children, truncated: t, .. }) in children {
+                truncated |= t;
+                level.extend(
+                    children
+                        .into_iter()
+                        .map(|child| SubtreeModel { path: format!("{parent}/{}", child.path), ..child }),
+                );
+            }
+        }
+        Ok(TreesModel { sha, url, tree, truncated })
     }
 
     pub async fn repository(&self) -> Result<models::Repository> {
-        let Self { owner, repo } = &self;
+        let Self { owner, repo, .. } = &self;
         let path = format!("/repos/{owner}/{repo}");
-        let request = Request::get(self.api_endpoint(&path)?.as_str());
-        Ok(request.send().await.map_err(anyhow::Error::from)?.json().await.map_err(anyhow::Error::from)?)
+        self.conditional_json(&self.api_endpoint(&path)?).await
+    }
+
+    /// `reference` is percent-encoded except its slashes, because the branch such as `feature/x` is also accepted
+    pub fn commits_endpoint(&self, reference: &str) -> Result<Url> {
+        let Self { owner, repo, .. } = &self;
+        let mut url = self.api_endpoint(&format!("/repos/{owner}/{repo}/commits"))?;
+        url.path_segments_mut().map_err(|_| anyhow::anyhow!(UrlParseError::Unspecified))?.extend(reference.split('/'));
+        Ok(url)
+    }
+
+    /// `reference` can be a branch, a tag, or a (short) commit SHA
+    pub async fn commits(&self, reference: &str) -> Result<CommitsModel> {
+        self.conditional_json(&self.commits_endpoint(reference)?).await
     }
 
-    pub async fn raw<A: AsRef<Path>>(&self, sha: &str, path: A) -> Result<String> {
-        let Self { owner, repo } = &self;
+    pub async fn raw<A: AsRef<Path>>(&self, sha: &str, path: A) -> Result<Vec<u8>> {
+        let Self { owner, repo, .. } = &self;
         let path = path.as_ref().to_str().ok_or_else(|| anyhow::anyhow!(Unreachable::UnimplementedString))?;
         let path = format!("/{owner}/{repo}/{sha}/{path}");
-        let request = Request::get(self.raw_endpoint(&path)?.as_str());
-        Ok(request.send().await.map_err(anyhow::Error::from)?.text().await.map_err(anyhow::Error::from)?)
+        let request = HttpRequest::get(self.raw_endpoint(&path)?);
+        Ok(self.send(request).await?.binary())
     }
 
-    pub async fn walk<'a>(&'a self, sha: &'a str) -> impl Stream<Item = Result<GitHubBlob>> + 'a {
-        // TODO zip or tar.gz
-        let TreesModel { tree, .. } = self.trees(&sha, true).await.unwrap();
-        let paths = tree.into_iter().filter_map(|SubtreeModel { path, contents_type, .. }| match contents_type {
-            ContentsType::Tree => None,
-            ContentsType::Blob => Some(PathBuf::from(path)),
-            ContentsType::Commit => None,
-        });
+    /// the content of the blob is base64 encoded, available for private repositories with the token
+    pub async fn blobs(&self, sha: &str) -> Result<BlobsModel> {
+        let Self { owner, repo, .. } = &self;
+        let path = format!("/repos/{owner}/{repo}/git/blobs/{sha}");
+        let request = self.api_request(&self.api_endpoint(&path)?);
+        self.send(request).await?.json()
+    }
 
+    /// the tarball is redirected to `codeload.github.com` (or the codeload of the enterprise server)
+    pub async fn tarball(&self, sha: &str) -> Result<Vec<u8>> {
+        let Self { owner, repo, .. } = &self;
+        let path = format!("/repos/{owner}/{repo}/tarball/{sha}");
+        let request = self.api_request(&self.api_endpoint(&path)?);
+        Ok(self.send(request).await?.binary())
+    }
+
+    /// files that are not regular in the tarball, such as symlinks, are fetched by per-file requests
+    pub async fn walk_tarball<'a>(
+        &'a self,
+        sha: &'a str,
+        blobs: Vec<SubtreeModel>,
+    ) -> Result<impl Stream<Item = Result<GitHubBlob>> + 'a> {
+        let tarball = self.tarball(sha).await?;
+        let paths = blobs.iter().map(|subtree| PathBuf::from(&subtree.path)).collect();
+        let decoded = TarballDecoder::new(&tarball).decode(&paths)?;
+        let found: HashSet<_> = decoded.iter().map(|blob| blob.path.clone()).collect();
+        let remaining = blobs.into_iter().filter(|subtree| !found.contains(Path::new(&subtree.path))).collect();
+        Ok(stream::iter(decoded.into_iter().map(Ok)).chain(self.walk_files(sha, remaining)))
+    }
+
+    /// raw files of private repositories cannot be fetched, so the GraphQL API is used if the token is given
+    pub fn walk_files<'a>(&'a self, sha: &'a str, blobs: Vec<SubtreeModel>) -> LocalBoxStream<'a, Result<GitHubBlob>> {
+        match self.token {
+            Some(_) => self.walk_graphql(sha, blobs).boxed_local(),
+            None => {
+                self.walk_raw(sha, blobs.into_iter().map(|subtree| PathBuf::from(subtree.path)).collect()).boxed_local()
+            }
+        }
+    }
+
+    /// many files are fetched by a query, the files that are not returned as text and the chunks whose query fails
+    /// are fetched by the blobs API
+    pub fn walk_graphql<'a>(
+        &'a self,
+        sha: &'a str,
+        blobs: Vec<SubtreeModel>,
+    ) -> impl Stream<Item = Result<GitHubBlob>> + 'a {
+        stream::iter(BlobsQuery::chunks(blobs))
+            .map(move |query| async move { (query.blobs.clone(), self.graphql_blobs(sha, query).await) })
+            .buffered(4)
+            .flat_map(move |(chunk, result)| match result {
+                Ok((blobs, remaining)) => {
+                    stream::iter(blobs.into_iter().map(Ok)).chain(self.walk_blobs(remaining)).boxed_local()
+                }
+                Err(err) if err.downcast_ref::<RateLimitError>().is_some() => {
+                    stream::once(async { Err(err) }).boxed_local()
+                }
+                Err(err) => {
+                    host::warn(&format!("fallback to the blobs API: {err}"));
+                    self.walk_blobs(chunk).boxed_local()
+                }
+            })
+    }
+
+    pub fn walk_blobs(&self, blobs: Vec<SubtreeModel>) -> impl Stream<Item = Result<GitHubBlob>> + '_ {
+        stream::iter(blobs)
+            .map(move |SubtreeModel { path, sha, .. }| async move {
+                GitHubBlob::from_model(PathBuf::from(path), self.blobs(&sha).await?)
+            })
+            .buffered(32)
+    }
+
+    pub fn walk_raw<'a>(&'a self, sha: &'a str, paths: Vec<PathBuf>) -> impl Stream<Item = Result<GitHubBlob>> + 'a {
         stream::iter(paths.clone())
-            .map(move |path| self.raw(&sha, path))
+            .map(move |path| self.raw(sha, path))
             .buffered(32) // num_cpus::get() returns 1
             .zip(stream::iter(paths))
             .map(|(raw, path)| Ok(GitHubBlob::new(path, raw?)))
             .map_ok(|blob| blob)
     }
+}
 
-    pub async fn get_statistics(&self, config: &tokei::Config) -> Result<Statistics> {
-        Statistics::get(self.clone(), config).await // TODO lifetime
+impl RepositoryHost for GitHubRepository {
+    fn host(&self) -> String {
+        self.name.clone()
+    }
+
+    fn owner(&self) -> String {
+        self.owner.clone()
+    }
+
+    fn repo(&self) -> String {
+        self.repo.clone()
+    }
+
+    fn from_url(url: &Url) -> Result<Self> {
+        if url.origin().unicode_serialization() != Self::ORIGIN {
+            Err(anyhow::anyhow!(UrlParseError::InvalidHost))?
+        }
+        let mut path_segments = url.path_segments().ok_or_else(|| anyhow::anyhow!(UrlParseError::Unspecified))?;
+        let owner = path_segments.next().ok_or_else(|| anyhow::anyhow!(UrlParseError::UnspecifiedOwner))?;
+        let repo = path_segments.next().ok_or_else(|| anyhow::anyhow!(UrlParseError::UnspecifiedRepository))?;
+        // TODO rest path
+        Ok(Self::new(owner, repo.trim_end_matches(".git")))
+    }
+
+    fn to_url(&self) -> Result<Url> {
+        Ok(Self::endpoint(&self.origins.origin, &format!("/{}/{}", self.owner, self.repo)))
+    }
+
+    fn tree_url(&self, sha: &str) -> Result<Url> {
+        let mut url = self.to_url()?;
+        url.path_segments_mut().map_err(|_| anyhow::anyhow!(UrlParseError::Unspecified))?.extend(["tree", sha]);
+        Ok(url)
+    }
+
+    async fn default_branch(&self) -> Result<String> {
+        Ok(self.repository().await?.default_branch.unwrap_or("master".to_string()))
+    }
+
+    async fn commit(&self, reference: &str) -> Result<Commit> {
+        let CommitsModel { sha, commit, .. } = self.commits(reference).await?;
+        Ok(Commit { reference: reference.to_string(), sha, message: commit.message })
+    }
+
+    async fn tree(&self, sha: &str, filter: &PathFilter) -> Result<TreesModel> {
+        self.entire_trees(sha, filter).await
+    }
+
+    async fn raw(&self, sha: &str, path: &Path) -> Result<Vec<u8>> {
+        GitHubRepository::raw(self, sha, path).await
+    }
+
+    /// raw files of private repositories cannot be fetched, so the blobs API is used if the token is given
+    async fn file(&self, sha: &str, subtree: &SubtreeModel) -> Result<Vec<u8>> {
+        match self.token {
+            Some(_) => BlobsModelDecoder::new(&self.blobs(&subtree.sha).await?).decode(),
+            None => GitHubRepository::raw(self, sha, &subtree.path).await,
+        }
+    }
+
+    async fn walk<'a>(
+        &'a self,
+        sha: &'a str,
+        tree: Vec<SubtreeModel>,
+        filter: &PathFilter,
+    ) -> LocalBoxStream<'a, Result<GitHubBlob>> {
+        let tree_size = tree.iter().filter_map(|subtree| subtree.size).sum();
+        let blobs = tree
+            .into_iter()
+            .filter(|subtree| match subtree.contents_type {
+                ContentsType::Tree => false,
+                ContentsType::Blob => true,
+                ContentsType::Commit => false,
+            })
+            .filter(|subtree| filter.is_included(&subtree.path, false))
+            .collect::<Vec<_>>();
+
+        if TarballDecoder::suitable(tree_size, blobs.len()) {
+            match self.walk_tarball(sha, blobs.clone()).await {
+                Ok(stream) => return stream.boxed_local(),
+                Err(err) if err.downcast_ref::<RateLimitError>().is_some() => {
+                    return stream::once(async { Err(err) }).boxed_local()
+                }
+                Err(err) => host::warn(&format!("fallback to per-file requests: {err}")),
+            }
+        }
+        self.walk_files(sha, blobs)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::host::instance::HostKind;
 
     #[test]
     fn test_parse_repo_url() {
@@ -123,4 +511,60 @@ mod tests {
         assert_eq!(repo, GitHubRepository::new("hayas1", "tokei-toukei"));
         assert_eq!(repo.to_url().unwrap().as_str(), "https://github.com/hayas1/tokei-toukei");
     }
+
+    #[test]
+    fn test_token_is_not_shown() {
+        let repo = GitHubRepository::new("hayas1", "tokei-toukei").with_token(Some(GitHubToken::new("ghp_secret")));
+        assert!(!format!("{repo:?}").contains("ghp_secret"));
+        assert_eq!(repo.token.unwrap().secret(), "ghp_secret");
+    }
+
+    #[test]
+    fn test_response_key() {
+        let url = Url::parse("https://api.github.com/repos/hayas1/tokei-toukei/commits/main").unwrap();
+        let repo = GitHubRepository::new("hayas1", "tokei-toukei");
+        assert_eq!(repo.response_key(&url), url.as_str());
+
+        let key = |token: &str| repo.clone().with_token(Some(GitHubToken::new(token))).response_key(&url);
+        assert_eq!(key("ghp_secret"), key("ghp_secret"));
+        assert_ne!(key("ghp_secret"), key("ghp_another"));
+        assert!(key("ghp_secret").starts_with(url.as_str()) && !key("ghp_secret").contains("ghp_secret"));
+    }
+
+    #[test]
+    fn test_enterprise_endpoints() {
+        let origin = Url::parse("https://git.corp.example").unwrap();
+        let instance = Instance::new("corp", HostKind::GitHub, origin);
+        let url = Url::parse("https://git.corp.example/team/service/tree/main").unwrap();
+        let repo = GitHubRepository::from_instance_url(&instance, &url).unwrap();
+        assert_eq!((repo.host(), repo.owner(), repo.repo()), ("corp".into(), "team".into(), "service".into()));
+        assert!(!repo.is_github_com());
+        assert_eq!(repo.to_url().unwrap().as_str(), "https://git.corp.example/team/service");
+        assert_eq!(
+            repo.api_endpoint("/repos/team/service").unwrap().as_str(),
+            "https://git.corp.example/api/v3/repos/team/service"
+        );
+        assert_eq!(
+            repo.raw_endpoint("/team/service/main/README.md").unwrap().as_str(),
+            "https://git.corp.example/raw/team/service/main/README.md"
+        );
+        assert_eq!(repo.graphql_endpoint().unwrap().as_str(), "https://git.corp.example/api/graphql");
+        assert!(GitHubRepository::from_url(&url).is_err());
+    }
+
+    #[test]
+    fn test_tree_url() {
+        let repo = GitHubRepository::new("hayas1", "tokei-toukei");
+        assert_eq!(repo.tree_url("v1.2.0").unwrap().as_str(), "https://github.com/hayas1/tokei-toukei/tree/v1.2.0");
+        assert_eq!(repo.graphql_endpoint().unwrap().as_str(), "https://api.github.com/graphql");
+    }
+
+    #[test]
+    fn test_commits_endpoint() {
+        let repo = GitHubRepository::new("hayas1", "tokei-toukei");
+        assert_eq!(
+            repo.commits_endpoint("feature/a#b?c%d").unwrap().as_str(),
+            "https://api.github.com/repos/hayas1/tokei-toukei/commits/feature/a%23b%3Fc%25d"
+        );
+    }
 }
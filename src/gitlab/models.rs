@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::github::models::{ContentsType, SubtreeModel};
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ProjectModel {
+    pub id: u64,
+    pub path_with_namespace: String,
+    pub default_branch: Option<String>,
+    pub web_url: Url,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CommitModel {
+    pub id: String,
+    pub short_id: String,
+    pub title: String,
+    pub message: String,
+    pub web_url: Url,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TreeModel {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub contents_type: ContentsType,
+    pub path: String,
+    pub mode: String,
+}
+impl From<TreeModel> for SubtreeModel {
+    fn from(TreeModel { id, contents_type, path, mode, .. }: TreeModel) -> Self {
+        // the repository tree API of GitLab does not return the size of blobs
+        Self { path, mode, contents_type, sha: id, size: None, url: None }
+    }
+}
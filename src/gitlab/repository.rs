@@ -0,0 +1,244 @@
+use std::path::Path;
+
+use url::Url;
+
+use crate::{
+    error::{
+        repository::{Unreachable, UrlParseError},
+        Result,
+    },
+    github::{models::TreesModel, rate_limit::RateLimit, repository::GitHubToken},
+    host::{
+        control::WalkControl,
+        fetch::{Fetch, Fetcher, HttpRequest, HttpResponse},
+        filter::PathFilter,
+        instance::{HostKind, Instance},
+        Commit, RepositoryHost,
+    },
+};
+
+use super::models::{CommitModel, ProjectModel, TreeModel};
+
+/// `owner` is the full path of the namespace, which may contain nested subgroups such as `group/subgroup`
+/// `name` is `gitlab` for gitlab.com, or the name of the self-managed instance
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitLabRepository {
+    pub name: String,
+    pub origin: Url,
+    pub owner: String,
+    pub repo: String,
+    /// personal access token of the instance, which is sent as `PRIVATE-TOKEN`
+    pub token: Option<GitHubToken>,
+    pub control: Option<WalkControl>,
+    pub fetcher: Fetcher,
+}
+
+impl GitLabRepository {
+    pub const ORIGIN: &'static str = "https://gitlab.com";
+    pub const PER_PAGE: usize = 100;
+
+    pub fn new(owner: &str, repo: &str) -> Self {
+        Self::instance(&Self::gitlab_com(), owner, repo)
+    }
+
+    /// repository of the self-managed instance, with the token of the instance
+    pub fn instance(instance: &Instance, owner: &str, repo: &str) -> Self {
+        let (name, origin, token) = (instance.name.clone(), instance.origin.clone(), instance.token.clone());
+        let (owner, repo) = (owner.to_string(), repo.to_string());
+        Self { name, origin, owner, repo, token, control: None, fetcher: Fetcher::default() }
+    }
+
+    fn gitlab_com() -> Instance {
+        let origin = Url::parse(Self::ORIGIN).expect("origin of gitlab.com should be valid url");
+        Instance::new("gitlab", HostKind::GitLab, origin)
+    }
+
+    /// parse the url such as `https://gitlab.example.com/{group}/{subgroup}/{project}/-/tree/main` of the instance
+    pub fn from_instance_url(instance: &Instance, url: &Url) -> Result<Self> {
+        // `/-/` separates the project path and the rest path such as `/-/tree/main`
+        let mut segments: Vec<_> = instance.relative_segments(url)?.into_iter().take_while(|s| s != "-").collect();
+        let repo = segments.pop().ok_or_else(|| anyhow::anyhow!(UrlParseError::UnspecifiedRepository))?;
+        if segments.is_empty() {
+            Err(anyhow::anyhow!(UrlParseError::UnspecifiedOwner))?
+        }
+        Ok(Self::instance(instance, &segments.join("/"), repo.trim_end_matches(".git")))
+    }
+
+    pub fn with_token(self, token: Option<GitHubToken>) -> Self {
+        Self { token, ..self }
+    }
+
+    pub fn with_control(self, control: Option<WalkControl>) -> Self {
+        Self { control, ..self }
+    }
+
+    pub fn with_fetcher(self, fetcher: Fetcher) -> Self {
+        Self { fetcher, ..self }
+    }
+
+    pub fn project_path(&self) -> String {
+        [&self.owner[..], &self.repo[..]].join("/")
+    }
+
+    /// `path` segments are appended to `/api/v4/projects/{url encoded project path}`
+    pub fn api_endpoint<'a, I: IntoIterator<Item = &'a str>>(&self, path: I) -> Result<Url> {
+        let mut url = self.origin.clone();
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!(UrlParseError::Unspecified))?
+            .pop_if_empty()
+            .extend(["api", "v4", "projects", &self.project_path()])
+            .extend(path);
+        Ok(url)
+    }
+
+    pub async fn send(&self, request: HttpRequest) -> Result<HttpResponse> {
+        let request = match &self.token {
+            Some(token) => request.header("PRIVATE-TOKEN", token.secret()),
+            None => request,
+        };
+        RateLimit::check(self.fetcher.fetch(request, self.control).await?)
+    }
+
+    pub async fn project(&self) -> Result<ProjectModel> {
+        let request = HttpRequest::get(self.api_endpoint([])?);
+        self.send(request).await?.json()
+    }
+
+    pub async fn commits(&self, reference: &str) -> Result<CommitModel> {
+        let request = HttpRequest::get(self.api_endpoint(["repository", "commits", reference])?);
+        self.send(request).await?.json()
+    }
+
+    /// one page of the recursive tree, and the next page if it exists
+    pub async fn trees(&self, sha: &str, page: usize) -> Result<(Vec<TreeModel>, Option<usize>)> {
+        let request = HttpRequest::get(self.api_endpoint(["repository", "tree"])?).query([
+            ("ref", sha.to_string()),
+            ("recursive", true.to_string()),
+            ("per_page", Self::PER_PAGE.to_string()),
+            ("page", page.to_string()),
+        ]);
+        let response = self.send(request).await?;
+        let next_page = response.header("x-next-page").and_then(|next| next.parse().ok());
+        Ok((response.json()?, next_page))
+    }
+
+    pub async fn raw<A: AsRef<Path>>(&self, sha: &str, path: A) -> Result<Vec<u8>> {
+        let path = path.as_ref().to_str().ok_or_else(|| anyhow::anyhow!(Unreachable::UnimplementedString))?;
+        let request = HttpRequest::get(self.api_endpoint(["repository", "files", path, "raw"])?).query([("ref", sha)]);
+        Ok(self.send(request).await?.binary())
+    }
+}
+
+impl RepositoryHost for GitLabRepository {
+    fn host(&self) -> String {
+        self.name.clone()
+    }
+
+    fn owner(&self) -> String {
+        self.owner.clone()
+    }
+
+    fn repo(&self) -> String {
+        self.repo.clone()
+    }
+
+    /// self-managed instances are registered as instances, they are not guessed from their domain
+    fn from_url(url: &Url) -> Result<Self> {
+        Self::from_instance_url(&Self::gitlab_com(), url)
+    }
+
+    fn to_url(&self) -> Result<Url> {
+        let mut url = self.origin.clone();
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!(UrlParseError::Unspecified))?
+            .pop_if_empty()
+            .extend(self.owner.split('/').chain([&self.repo[..]]));
+        Ok(url)
+    }
+
+    fn tree_url(&self, sha: &str) -> Result<Url> {
+        let mut url = self.to_url()?;
+        url.path_segments_mut().map_err(|_| anyhow::anyhow!(UrlParseError::Unspecified))?.extend(["-", "tree", sha]);
+        Ok(url)
+    }
+
+    async fn default_branch(&self) -> Result<String> {
+        Ok(self.project().await?.default_branch.unwrap_or("main".to_string()))
+    }
+
+    async fn commit(&self, reference: &str) -> Result<Commit> {
+        let CommitModel { id, message, .. } = self.commits(reference).await?;
+        Ok(Commit { reference: reference.to_string(), sha: id, message })
+    }
+
+    async fn tree(&self, sha: &str, _filter: &PathFilter) -> Result<TreesModel> {
+        let mut tree = Vec::new();
+        let mut page = Some(1);
+        while let Some(current) = page {
+            let (trees, next_page) = self.trees(sha, current).await?;
+            tree.extend(trees.into_iter().map(Into::into));
+            page = next_page;
+        }
+        let url = self.api_endpoint(["repository", "tree"])?;
+        Ok(TreesModel { sha: sha.to_string(), url, tree, truncated: false })
+    }
+
+    async fn raw(&self, sha: &str, path: &Path) -> Result<Vec<u8>> {
+        GitLabRepository::raw(self, sha, path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repo_url() {
+        let url = Url::parse("https://gitlab.com/group/subgroup/project").unwrap();
+        let repo = GitLabRepository::from_url(&url).unwrap();
+        assert_eq!(repo, GitLabRepository::new("group/subgroup", "project"));
+        assert_eq!(repo.host(), "gitlab");
+        assert_eq!(repo.to_url().unwrap().as_str(), "https://gitlab.com/group/subgroup/project");
+
+        let url = Url::parse("https://gitlab.com/group/project.git/-/tree/main").unwrap();
+        let repo = GitLabRepository::from_url(&url).unwrap();
+        assert_eq!((repo.owner(), repo.repo()), ("group".into(), "project".into()));
+
+        let url = Url::parse("https://gitlab.example.com/group/project").unwrap();
+        assert!(GitLabRepository::from_url(&url).is_err());
+
+        let url = Url::parse("https://gitlab.com/project").unwrap();
+        assert!(GitLabRepository::from_url(&url).is_err());
+        let url = Url::parse("https://github.com/hayas1/tokei-toukei").unwrap();
+        assert!(GitLabRepository::from_url(&url).is_err());
+    }
+
+    #[test]
+    fn test_self_managed_instance() {
+        let origin = Url::parse("https://code.example.org/git").unwrap();
+        let token = Some(GitHubToken::new("glpat_secret"));
+        let instance = Instance::new("corp", HostKind::GitLab, origin).with_token(token.clone());
+        let url = Url::parse("https://code.example.org/git/group/subgroup/project/-/tree/main").unwrap();
+        let repo = GitLabRepository::from_instance_url(&instance, &url).unwrap();
+        assert_eq!(
+            (repo.host(), repo.owner(), repo.repo()),
+            ("corp".into(), "group/subgroup".into(), "project".into())
+        );
+        assert_eq!(repo.token, token);
+        assert_eq!(repo.to_url().unwrap().as_str(), "https://code.example.org/git/group/subgroup/project");
+        assert_eq!(
+            repo.api_endpoint(["repository", "tree"]).unwrap().as_str(),
+            "https://code.example.org/git/api/v4/projects/group%2Fsubgroup%2Fproject/repository/tree"
+        );
+    }
+
+    #[test]
+    fn test_api_endpoint() {
+        let repo = GitLabRepository::new("group/subgroup", "project");
+        assert_eq!(
+            repo.api_endpoint(["repository", "files", "src/main.rs", "raw"]).unwrap().as_str(),
+            "https://gitlab.com/api/v4/projects/group%2Fsubgroup%2Fproject/repository/files/src%2Fmain.rs/raw"
+        );
+        assert_eq!(repo.tree_url("main").unwrap().as_str(), "https://gitlab.com/group/subgroup/project/-/tree/main");
+    }
+}
@@ -0,0 +1,365 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use futures::future::try_join_all;
+use js_sys::{Array, Function, JsString, Promise};
+use once_cell::sync::OnceCell;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tokei::{CodeStats, LanguageType};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbObjectStore, IdbRequest, IdbTransactionMode};
+
+use crate::error::{cache::CacheError, Result};
+
+use super::statistics::Statistics;
+
+/// per-file result that is cached by the git blob SHA, so the same content is not fetched again in any repository
+/// a text blob keeps the stats for each language it is parsed as, because the language depends on the path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum CachedBlob {
+    Binary { bytes: u64 },
+    Text { bytes: u64, shebang: Option<LanguageType>, stats: BTreeMap<LanguageType, CodeStats> },
+}
+
+/// result of the cached blob for the path, which is counted without fetching the blob
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lookup {
+    Binary,
+    /// the language is not recognized, so the blob is not counted
+    Unknown,
+    Counted(LanguageType, CodeStats),
+}
+
+impl CachedBlob {
+    pub fn text(text: &str, bytes: u64) -> Self {
+        Self::Text { bytes, shebang: Statistics::from_shebang(text), stats: BTreeMap::new() }
+    }
+
+    pub fn bytes(&self) -> u64 {
+        match self {
+            Self::Binary { bytes } | Self::Text { bytes, .. } => *bytes,
+        }
+    }
+
+    /// `None` if the blob should be fetched, because it is not parsed as the language of the path yet
+    pub fn lookup(&self, path: &Path, executable: bool, config: &tokei::Config) -> Option<Lookup> {
+        match self {
+            Self::Binary { .. } => Some(Lookup::Binary),
+            Self::Text { shebang, stats, .. } => {
+                let language_type = LanguageType::from_path(path, config).or(shebang.filter(|_| executable));
+                match language_type {
+                    Some(language_type) => stats.get(&language_type).map(|s| Lookup::Counted(language_type, s.clone())),
+                    None => Some(Lookup::Unknown),
+                }
+            }
+        }
+    }
+
+    /// `None` if the blob is not parsed as the language yet, such as the one of `linguist-language`
+    pub fn lookup_as(&self, language_type: LanguageType) -> Option<Lookup> {
+        match self {
+            Self::Binary { .. } => Some(Lookup::Binary),
+            Self::Text { stats, .. } => stats.get(&language_type).map(|s| Lookup::Counted(language_type, s.clone())),
+        }
+    }
+
+    /// the stats of the other languages that are already cached are kept
+    pub fn with_stats(self, language_type: LanguageType, stats: CodeStats) -> Self {
+        match self {
+            Self::Text { bytes, shebang, stats: mut cached } => {
+                cached.insert(language_type, stats);
+                Self::Text { bytes, shebang, stats: cached }
+            }
+            binary => binary,
+        }
+    }
+}
+
+/// response of the API that is cached with its ETag, the body is served when the response is `304 Not Modified`
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub etag: String,
+    pub body: String,
+}
+
+/// IndexedDB of the browser, or the directory of the native server, that stores the blobs keyed by their SHA and the
+/// API responses keyed by their url, as JSON strings
+#[derive(Debug, Clone)]
+pub enum Cache {
+    IndexedDb(IdbDatabase),
+    /// each entry is a file in the subdirectory of its store, whose name is the url encoded key
+    Disk(PathBuf),
+}
+
+/// number of the cached entries and the approximate size of them
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub struct CacheUsage {
+    pub entries: usize,
+    pub bytes: u64,
+}
+
+/// the directory is given only natively, such as by the option of the server
+static DIRECTORY: OnceCell<PathBuf> = OnceCell::new();
+/// suffix of the temporary files, so that the concurrent writes do not conflict
+static TEMPORARY: AtomicU64 = AtomicU64::new(0);
+
+impl Cache {
+    pub const DATABASE: &'static str = concat!(env!("CARGO_PKG_NAME"), "/cache");
+    pub const BLOBS: &'static str = "blobs";
+    pub const RESPONSES: &'static str = "responses";
+    pub const STORES: [&'static str; 2] = [Self::BLOBS, Self::RESPONSES];
+    pub const VERSION: u32 = 2;
+    /// the fetched blobs are stored in a transaction for each this number of blobs
+    pub const FLUSH_SIZE: usize = 256;
+    /// the entries whose encoded key is longer than this are not stored on the disk, because of the file name limit
+    pub const MAX_KEY_LENGTH: usize = 200;
+
+    fn js_error(err: JsValue) -> anyhow::Error {
+        anyhow::anyhow!(CacheError::IndexedDb(format!("{err:?}")))
+    }
+
+    /// store the cache in the directory instead of IndexedDB, it can be set only once
+    pub fn set_directory(directory: PathBuf) -> Result<()> {
+        for store in Self::STORES {
+            fs::create_dir_all(directory.join(store)).map_err(anyhow::Error::from)?;
+        }
+        DIRECTORY
+            .set(directory)
+            .map_err(|d| anyhow::anyhow!(CacheError::DirectoryAlreadySet(d.display().to_string())))?;
+        Ok(())
+    }
+
+    /// IndexedDB is available only in the browser, so the cache is unavailable natively unless the directory is set
+    pub async fn open() -> Result<Self> {
+        match DIRECTORY.get() {
+            Some(directory) => Ok(Self::Disk(directory.clone())),
+            None if cfg!(target_arch = "wasm32") => Self::open_indexed_db().await,
+            None => Err(anyhow::anyhow!(CacheError::Unavailable))?,
+        }
+    }
+
+    async fn open_indexed_db() -> Result<Self> {
+        let factory = gloo::utils::window()
+            .indexed_db()
+            .map_err(Self::js_error)?
+            .ok_or_else(|| anyhow::anyhow!(CacheError::Unavailable))?;
+        let request = factory.open_with_u32(Self::DATABASE, Self::VERSION).map_err(Self::js_error)?;
+        let onupgradeneeded = Closure::<dyn FnMut(JsValue)>::new({
+            let request = request.clone();
+            move |_| {
+                if let Ok(database) = request.result().map(JsCast::unchecked_into::<IdbDatabase>) {
+                    // creating the store that already exists fails, it is just ignored
+                    Self::STORES.iter().for_each(|store| drop(database.create_object_store(store)));
+                }
+            }
+        });
+        request.set_onupgradeneeded(Some(onupgradeneeded.as_ref().unchecked_ref()));
+        let database = Self::request(&request).await;
+        request.set_onupgradeneeded(None);
+        Ok(Self::IndexedDb(database?.unchecked_into()))
+    }
+
+    /// the callbacks of the request are bridged into a future
+    async fn request(request: &IdbRequest) -> Result<JsValue> {
+        let promise = Promise::new(&mut |resolve: Function, reject: Function| {
+            let onsuccess = Closure::once_into_js({
+                let request = request.clone();
+                move |_: JsValue| resolve.call1(&JsValue::NULL, &request.result().unwrap_or(JsValue::UNDEFINED))
+            });
+            let onerror = Closure::once_into_js(move |event: JsValue| reject.call1(&JsValue::NULL, &event));
+            request.set_onsuccess(Some(onsuccess.unchecked_ref()));
+            request.set_onerror(Some(onerror.unchecked_ref()));
+        });
+        Ok(JsFuture::from(promise).await.map_err(Self::js_error)?)
+    }
+
+    fn store(database: &IdbDatabase, name: &str, mode: IdbTransactionMode) -> Result<IdbObjectStore> {
+        let transaction = database.transaction_with_str_and_mode(name, mode).map_err(Self::js_error)?;
+        Ok(transaction.object_store(name).map_err(Self::js_error)?)
+    }
+
+    fn path(directory: &Path, name: &str, key: &str) -> Option<PathBuf> {
+        let file: String = url::form_urlencoded::byte_serialize(key.as_bytes()).collect();
+        (file.len() <= Self::MAX_KEY_LENGTH).then(|| directory.join(name).join(file))
+    }
+
+    /// the values are `None` if they are not stored or cannot be deserialized
+    async fn get_values<T: DeserializeOwned>(&self, name: &str, keys: &[&str]) -> Result<Vec<Option<T>>> {
+        let values = match self {
+            Self::IndexedDb(database) => {
+                let store = Self::store(database, name, IdbTransactionMode::Readonly)?;
+                let requests = keys
+                    .iter()
+                    .map(|&key| Ok(store.get(&JsValue::from_str(key)).map_err(Self::js_error)?))
+                    .collect::<Result<Vec<_>>>()?;
+                let values = try_join_all(requests.iter().map(Self::request)).await?;
+                values.into_iter().map(|value| value.as_string()).collect()
+            }
+            Self::Disk(directory) => {
+                keys.iter().map(|key| fs::read_to_string(Self::path(directory, name, key)?).ok()).collect::<Vec<_>>()
+            }
+        };
+        Ok(values.into_iter().map(|value| serde_json::from_str(&value?).ok()).collect())
+    }
+
+    async fn put_values<T: Serialize>(&self, name: &str, values: &[(&str, &T)]) -> Result<()> {
+        let values = values
+            .iter()
+            .map(|(key, value)| Ok((*key, serde_json::to_string(value).map_err(anyhow::Error::from)?)))
+            .collect::<Result<Vec<_>>>()?;
+        match self {
+            Self::IndexedDb(database) => {
+                let store = Self::store(database, name, IdbTransactionMode::Readwrite)?;
+                let requests = values
+                    .iter()
+                    .map(|(key, value)| {
+                        Ok(store
+                            .put_with_key(&JsValue::from_str(value), &JsValue::from_str(key))
+                            .map_err(Self::js_error)?)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                try_join_all(requests.iter().map(Self::request)).await?;
+            }
+            Self::Disk(directory) => {
+                for (key, value) in values {
+                    let Some(path) = Self::path(directory, name, key) else { continue };
+                    // the entry is replaced atomically, so that the concurrent reads do not see a partial file
+                    let temporary = TEMPORARY.fetch_add(1, Ordering::Relaxed);
+                    let temporary = directory.join(name).join(format!(".{}-{temporary}.tmp", std::process::id()));
+                    fs::write(&temporary, value).map_err(anyhow::Error::from)?;
+                    fs::rename(&temporary, path).map_err(anyhow::Error::from)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// the blobs that are not cached are not included
+    pub async fn get_blobs<'a, I: IntoIterator<Item = &'a str>>(&self, shas: I) -> Result<HashMap<String, CachedBlob>> {
+        let shas: Vec<_> = shas.into_iter().collect();
+        let blobs = self.get_values(Self::BLOBS, &shas).await?;
+        Ok(shas.into_iter().zip(blobs).filter_map(|(sha, blob)| Some((sha.to_string(), blob?))).collect())
+    }
+
+    pub async fn put_blobs(&self, blobs: &HashMap<String, CachedBlob>) -> Result<()> {
+        let blobs: Vec<_> = blobs.iter().map(|(sha, blob)| (&sha[..], blob)).collect();
+        self.put_values(Self::BLOBS, &blobs).await
+    }
+
+    pub async fn get_response(&self, url: &str) -> Result<Option<CachedResponse>> {
+        Ok(self.get_values(Self::RESPONSES, &[url]).await?.pop().flatten())
+    }
+
+    pub async fn put_response(&self, url: &str, response: &CachedResponse) -> Result<()> {
+        self.put_values(Self::RESPONSES, &[(url, response)]).await
+    }
+
+    /// the entries of the stores on the disk, the temporary files are not included
+    fn entries(directory: &Path) -> Result<Vec<PathBuf>> {
+        let mut entries = Vec::new();
+        for name in Self::STORES {
+            for entry in fs::read_dir(directory.join(name)).map_err(anyhow::Error::from)? {
+                let path = entry.map_err(anyhow::Error::from)?.path();
+                if !path.file_name().is_some_and(|file| file.to_string_lossy().starts_with('.')) {
+                    entries.push(path);
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// the size is the length of the stored JSON strings of all stores
+    pub async fn usage(&self) -> Result<CacheUsage> {
+        let mut usage = CacheUsage::default();
+        match self {
+            Self::IndexedDb(database) => {
+                for name in Self::STORES {
+                    let store = Self::store(database, name, IdbTransactionMode::Readonly)?;
+                    let values: Array =
+                        Self::request(&store.get_all().map_err(Self::js_error)?).await?.unchecked_into();
+                    usage.entries += values.length() as usize;
+                    usage.bytes +=
+                        values.iter().map(|value| value.unchecked_into::<JsString>().length() as u64).sum::<u64>();
+                }
+            }
+            Self::Disk(directory) => {
+                for entry in Self::entries(directory)? {
+                    usage.entries += 1;
+                    usage.bytes += fs::metadata(entry).map_or(0, |metadata| metadata.len());
+                }
+            }
+        }
+        Ok(usage)
+    }
+
+    pub async fn clear(&self) -> Result<()> {
+        match self {
+            Self::IndexedDb(database) => {
+                for name in Self::STORES {
+                    let store = Self::store(database, name, IdbTransactionMode::Readwrite)?;
+                    Self::request(&store.clear().map_err(Self::js_error)?).await?;
+                }
+            }
+            Self::Disk(directory) => {
+                for entry in Self::entries(directory)? {
+                    fs::remove_file(entry).map_err(anyhow::Error::from)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+
+    #[test]
+    fn test_lookup_cached_blob() {
+        let config = tokei::Config::default();
+        let stats = LanguageType::Rust.parse_from_slice(b"fn main() {}\n// comment\n", &config);
+        let cached = CachedBlob::text("#!/usr/bin/env python3\n", 24).with_stats(LanguageType::Rust, stats.clone());
+
+        let json = serde_json::to_string(&cached).unwrap();
+        assert_eq!(serde_json::from_str::<CachedBlob>(&json).unwrap(), cached);
+
+        assert_eq!(
+            cached.lookup(Path::new("src/main.rs"), false, &config),
+            Some(Lookup::Counted(LanguageType::Rust, stats))
+        );
+        assert_eq!(cached.lookup(Path::new("src/main.c"), false, &config), None);
+        assert_eq!(cached.lookup(Path::new("bin/run"), false, &config), Some(Lookup::Unknown));
+        assert_eq!(cached.lookup(Path::new("bin/run"), true, &config), None);
+        assert_eq!(CachedBlob::Binary { bytes: 8 }.lookup(Path::new("logo.png"), false, &config), Some(Lookup::Binary));
+    }
+
+    #[test]
+    fn test_disk_cache() {
+        let directory = std::env::temp_dir().join(format!("tokei-toukei-cache-{}", std::process::id()));
+        Cache::STORES.iter().for_each(|store| fs::create_dir_all(directory.join(store)).unwrap());
+        let cache = Cache::Disk(directory.clone());
+
+        let blobs = HashMap::from([("4a1e7c3d".to_string(), CachedBlob::Binary { bytes: 8 })]);
+        block_on(cache.put_blobs(&blobs)).unwrap();
+        assert_eq!(block_on(cache.get_blobs(["4a1e7c3d", "f5e6a7b8"])).unwrap(), blobs);
+
+        let url = "https://api.github.com/repos/hayas1/tokei-toukei?recursive=true";
+        let response = CachedResponse { etag: "\"etag\"".to_string(), body: "{}".to_string() };
+        block_on(cache.put_response(url, &response)).unwrap();
+        block_on(cache.put_response(&"long".repeat(Cache::MAX_KEY_LENGTH), &response)).unwrap();
+        assert_eq!(block_on(cache.get_response(url)).unwrap(), Some(response));
+        assert_eq!(block_on(cache.usage()).unwrap().entries, 2);
+
+        block_on(cache.clear()).unwrap();
+        assert_eq!(block_on(cache.usage()).unwrap(), CacheUsage::default());
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
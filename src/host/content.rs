@@ -0,0 +1,104 @@
+use encoding_rs::{Encoding, EUC_JP, SHIFT_JIS, UTF_8, WINDOWS_1252};
+
+/// content of the blob, which is decoded into UTF-8 unless it is binary
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Content {
+    Binary,
+    Text { text: String, encoding: &'static Encoding },
+}
+impl Content {
+    /// NUL bytes are searched in the leading bytes like git does
+    pub const SNIFF_LENGTH: usize = 8000;
+
+    /// magic numbers of the binary formats that may not contain NUL bytes in the leading bytes
+    pub const MAGIC_NUMBERS: &'static [&'static [u8]] = &[
+        b"\x89PNG\r\n\x1a\n",
+        b"GIF87a",
+        b"GIF89a",
+        b"\xff\xd8\xff",
+        b"%PDF-",
+        b"PK\x03\x04",
+        b"\x1f\x8b",
+        b"\x7fELF",
+        b"\0asm",
+        b"\xca\xfe\xba\xbe",
+        b"\xcf\xfa\xed\xfe",
+    ];
+
+    pub fn decode(bytes: &[u8]) -> Self {
+        if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+            let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+            return Self::Text { text: text.into_owned(), encoding };
+        }
+        if Self::is_binary(bytes) {
+            return Self::Binary;
+        }
+        let encoding = Self::detect(bytes);
+        let (text, _) = encoding.decode_without_bom_handling(bytes);
+        Self::Text { text: text.into_owned(), encoding }
+    }
+
+    /// the content with BOM is not binary even if it contains NUL bytes such as UTF-16
+    pub fn is_binary(bytes: &[u8]) -> bool {
+        let leading = &bytes[..bytes.len().min(Self::SNIFF_LENGTH)];
+        Self::MAGIC_NUMBERS.iter().any(|magic| bytes.starts_with(magic)) || leading.contains(&0)
+    }
+
+    /// UTF-8 is preferred, then the Japanese encodings, and Latin-1 decodes any bytes
+    pub fn detect(bytes: &[u8]) -> &'static Encoding {
+        if std::str::from_utf8(bytes).is_ok() {
+            return UTF_8;
+        }
+        [SHIFT_JIS, EUC_JP]
+            .into_iter()
+            .filter_map(|encoding| {
+                let text = encoding.decode_without_bom_handling_and_without_replacement(bytes)?;
+                Some((Self::japanese_chars(&text), encoding))
+            })
+            .filter(|&(score, _)| score > 0)
+            .max_by_key(|&(score, _)| score)
+            .map_or(WINDOWS_1252, |(_, encoding)| encoding)
+    }
+
+    /// kana, kanji, and Japanese punctuations, which are produced only by the correct encoding
+    fn japanese_chars(text: &str) -> usize {
+        text.chars()
+            .filter(|c| matches!(c, '\u{3040}'..='\u{30ff}' | '\u{4e00}'..='\u{9fff}' | '\u{3000}'..='\u{303f}'))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(content: Content) -> (String, &'static str) {
+        match content {
+            Content::Text { text, encoding } => (text, encoding.name()),
+            Content::Binary => panic!("should be text"),
+        }
+    }
+
+    #[test]
+    fn test_detect_binary() {
+        assert_eq!(Content::decode(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Content::Binary);
+        assert_eq!(Content::decode(b"GIF89a..."), Content::Binary);
+        assert_eq!(Content::decode(b"text\0with nul"), Content::Binary);
+        assert_eq!(text(Content::decode(b"fn main() {}\n")), ("fn main() {}\n".to_string(), "UTF-8"));
+    }
+
+    #[test]
+    fn test_detect_encoding() {
+        let source = "// 日本語のコメント\nint main() { return 0; }\n";
+        for encoding in [SHIFT_JIS, EUC_JP] {
+            let (bytes, _, _) = encoding.encode(source);
+            assert_eq!(text(Content::decode(&bytes)), (source.to_string(), encoding.name()));
+        }
+
+        let utf16: Vec<u8> = [0xff, 0xfe].into_iter().chain(source.encode_utf16().flat_map(u16::to_le_bytes)).collect();
+        assert_eq!(text(Content::decode(&utf16)), (source.to_string(), "UTF-16LE"));
+
+        let (latin1, _, _) = WINDOWS_1252.encode("/* caf\u{e9} */\n");
+        assert_eq!(text(Content::decode(&latin1)), ("/* caf\u{e9} */\n".to_string(), "windows-1252"));
+    }
+}
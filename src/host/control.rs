@@ -0,0 +1,134 @@
+use std::{cell::RefCell, collections::HashMap};
+
+use futures::channel::oneshot;
+use gloo::net::http::RequestBuilder;
+use web_sys::{AbortController, AbortSignal};
+
+use crate::error::{repository::AbortError, Result};
+
+thread_local! {
+    /// the controllers are not `Send`, so the repositories hold only the id of them
+    static CONTROLS: RefCell<Controls> = RefCell::new(Controls::default());
+}
+
+#[derive(Default)]
+struct Controls {
+    next: u32,
+    controls: HashMap<u32, Control>,
+}
+
+/// `waiters` are notified when the walk is resumed, and they are dropped when the walk is aborted
+struct Control {
+    controller: AbortController,
+    paused: bool,
+    waiters: Vec<oneshot::Sender<()>>,
+}
+
+/// handle to abort or pause the requests of a walk, which is shared by the repository and the view
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct WalkControl {
+    id: u32,
+}
+impl WalkControl {
+    pub fn new() -> Result<Self> {
+        let controller = AbortController::new().map_err(|err| anyhow::anyhow!("{err:?}"))?;
+        let id = CONTROLS.with_borrow_mut(|Controls { next, controls }| {
+            let id = *next;
+            *next = next.wrapping_add(1);
+            controls.insert(id, Control { controller, paused: false, waiters: Vec::new() });
+            id
+        });
+        Ok(Self { id })
+    }
+
+    /// the signal to be attached to the requests, it is an error if the walk is already aborted or released
+    pub fn signal(&self) -> Result<AbortSignal> {
+        let signal = CONTROLS
+            .with_borrow(|Controls { controls, .. }| controls.get(&self.id).map(|control| control.controller.signal()));
+        Ok(signal.ok_or_else(|| anyhow::anyhow!(AbortError::Aborted))?)
+    }
+
+    /// attach the signal to the request, so that it is aborted with the walk
+    pub fn attach(&self, request: RequestBuilder) -> Result<RequestBuilder> {
+        Ok(request.abort_signal(Some(&self.signal()?)))
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        CONTROLS.with_borrow(|Controls { controls, .. }| !controls.contains_key(&self.id))
+    }
+
+    /// abort the requests in flight, the following requests are rejected without being sent
+    pub fn abort(&self) {
+        if let Some(control) = self.take() {
+            control.controller.abort();
+        }
+    }
+
+    /// forget the control after the walk is completed, the requests in flight are not aborted
+    pub fn release(&self) {
+        self.take();
+    }
+
+    fn take(&self) -> Option<Control> {
+        CONTROLS.with_borrow_mut(|Controls { controls, .. }| controls.remove(&self.id))
+    }
+
+    pub fn is_paused(&self) -> bool {
+        CONTROLS.with_borrow(|Controls { controls, .. }| controls.get(&self.id).is_some_and(|control| control.paused))
+    }
+
+    pub fn pause(&self) {
+        self.set_paused(true);
+    }
+
+    pub fn resume(&self) {
+        self.set_paused(false);
+    }
+
+    fn set_paused(&self, paused: bool) {
+        CONTROLS.with_borrow_mut(|Controls { controls, .. }| {
+            if let Some(control) = controls.get_mut(&self.id) {
+                control.paused = paused;
+                if !paused {
+                    // the waiter may be already dropped with its walk
+                    control.waiters.drain(..).for_each(|waiter| waiter.send(()).unwrap_or_default());
+                }
+            }
+        })
+    }
+
+    /// wait until the walk is resumed, the requests are not sent while it is paused
+    pub async fn resumed(&self) -> Result<()> {
+        let waiter = CONTROLS.with_borrow_mut(|Controls { controls, .. }| match controls.get_mut(&self.id) {
+            Some(Control { paused: true, waiters, .. }) => {
+                let (sender, receiver) = oneshot::channel();
+                waiters.push(sender);
+                Ok(Some(receiver))
+            }
+            Some(_) => Ok(None),
+            None => Err(anyhow::anyhow!(AbortError::Aborted)),
+        })?;
+        match waiter {
+            Some(receiver) => Ok(receiver.await.map_err(|_| anyhow::anyhow!(AbortError::Aborted))?),
+            None => Ok(()),
+        }
+    }
+}
+
+/// the control that is aborted when it is dropped, for the owner that may be destroyed without its cleanup, such as
+/// the component that is unmounted while it is suspended
+#[derive(Debug, PartialEq)]
+pub struct AbortGuard(pub Option<WalkControl>);
+impl Default for AbortGuard {
+    /// the guard without the control if the controller is not available
+    fn default() -> Self {
+        Self(WalkControl::new().ok())
+    }
+}
+impl Drop for AbortGuard {
+    fn drop(&mut self) {
+        if let Some(control) = self.0 {
+            control.abort();
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use std::{collections::HashMap, sync::Arc};
+
+use gloo::net::http::Request;
+#[cfg(not(target_arch = "wasm32"))]
+use once_cell::sync::OnceCell;
+use serde::{de::DeserializeOwned, Serialize};
+use url::Url;
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::error::repository::AbortError;
+use crate::error::Result;
+
+use super::control::WalkControl;
+#[cfg(not(target_arch = "wasm32"))]
+use super::pool::WorkerPool;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+/// request that is sent by the fetcher, the query is a part of the url
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: Url,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+impl HttpRequest {
+    pub fn get(url: Url) -> Self {
+        Self { method: Method::Get, url, headers: Vec::new(), body: None }
+    }
+
+    pub fn post<T: Serialize>(url: Url, body: &T) -> Result<Self> {
+        let body = serde_json::to_string(body).map_err(anyhow::Error::from)?;
+        let request = Self { method: Method::Post, url, headers: Vec::new(), body: Some(body) };
+        Ok(request.header("Content-Type", "application/json"))
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn query<'a, I: IntoIterator<Item = (&'a str, V)>, V: AsRef<str>>(mut self, pairs: I) -> Self {
+        self.url.query_pairs_mut().extend_pairs(pairs);
+        self
+    }
+}
+
+/// response of the fetcher, whose body is read entirely and whose header names are lowercase
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub url: String,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+impl HttpResponse {
+    pub fn new(status: u16) -> Self {
+        Self { status, ..Default::default() }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_lowercase(), value.to_string());
+        self
+    }
+
+    pub fn with_body<B: Into<Vec<u8>>>(self, body: B) -> Self {
+        Self { body: body.into(), ..self }
+    }
+
+    pub fn ok(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn header(&self, name: &str) -> Option<String> {
+        self.headers.get(&name.to_lowercase()).cloned()
+    }
+
+    pub fn text(&self) -> Result<String> {
+        Ok(String::from_utf8(self.body.clone()).map_err(anyhow::Error::from)?)
+    }
+
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_slice(&self.body).map_err(anyhow::Error::from)?)
+    }
+
+    pub fn binary(self) -> Vec<u8> {
+        self.body
+    }
+}
+
+/// HTTP layer of the repositories, the control aborts the request if the fetcher supports it
+#[allow(async_fn_in_trait)] // futures run on the single thread of wasm, so `Send` is not required
+pub trait Fetch {
+    async fn fetch(&self, request: HttpRequest, control: Option<WalkControl>) -> Result<HttpResponse>;
+}
+
+/// `fetch` of the browser
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub struct BrowserFetcher;
+impl Fetch for BrowserFetcher {
+    async fn fetch(&self, request: HttpRequest, control: Option<WalkControl>) -> Result<HttpResponse> {
+        let HttpRequest { method, url, headers, body } = request;
+        let builder = match method {
+            Method::Get => Request::get(url.as_str()),
+            Method::Post => Request::post(url.as_str()),
+        };
+        let builder = headers.iter().fold(builder, |builder, (name, value)| builder.header(name, value));
+        let builder = match control {
+            Some(control) => control.attach(builder)?,
+            None => builder,
+        };
+        let request = match body {
+            Some(body) => builder.body(body),
+            None => builder.build(),
+        };
+        let response = request.map_err(anyhow::Error::from)?.send().await.map_err(anyhow::Error::from)?;
+        let (status, url) = (response.status(), response.url());
+        let headers = response.headers().entries().map(|(name, value)| (name.to_lowercase(), value)).collect();
+        let body = response.binary().await.map_err(anyhow::Error::from)?;
+        Ok(HttpResponse { status, url, headers, body })
+    }
+}
+
+/// requests for the command line, the error statuses are also returned as the responses, the blocking requests run
+/// on the pool, so that the requests of a walk are sent concurrently
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub struct NativeFetcher;
+#[cfg(not(target_arch = "wasm32"))]
+impl NativeFetcher {
+    /// as many as the requests that a walk buffers
+    pub const WORKERS: usize = 32;
+
+    fn pool() -> &'static WorkerPool {
+        static POOL: OnceCell<WorkerPool> = OnceCell::new();
+        POOL.get_or_init(|| WorkerPool::new(Self::WORKERS))
+    }
+
+    fn send(request: HttpRequest) -> Result<HttpResponse> {
+        use std::io::Read;
+
+        let HttpRequest { method, url, headers, body } = request;
+        let method = match method {
+            Method::Get => "GET",
+            Method::Post => "POST",
+        };
+        let request =
+            headers.iter().fold(ureq::request(method, url.as_str()), |request, (name, value)| request.set(name, value));
+        let sent = match body {
+            Some(body) => request.send_string(&body),
+            None => request.call(),
+        };
+        let response = match sent {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(err) => Err(anyhow::Error::from(err))?,
+        };
+        let (status, url) = (response.status(), response.get_url().to_string());
+        let headers = response
+            .headers_names()
+            .into_iter()
+            .filter_map(|name| Some((name.to_lowercase(), response.header(&name)?.to_string())))
+            .collect();
+        let mut body = Vec::new();
+        response.into_reader().read_to_end(&mut body).map_err(anyhow::Error::from)?;
+        Ok(HttpResponse { status, url, headers, body })
+    }
+}
+#[cfg(not(target_arch = "wasm32"))]
+impl Fetch for NativeFetcher {
+    /// the request in flight cannot be aborted, so the response is discarded if the walk is aborted meanwhile
+    async fn fetch(&self, request: HttpRequest, control: Option<WalkControl>) -> Result<HttpResponse> {
+        let aborted = || control.is_some_and(|control| control.is_aborted());
+        if aborted() {
+            Err(anyhow::anyhow!(AbortError::Aborted))?
+        }
+        let response = Self::pool().spawn(move || Self::send(request)).await?;
+        match aborted() {
+            true => Err(anyhow::anyhow!(AbortError::Aborted))?,
+            false => Ok(response),
+        }
+    }
+}
+
+/// recorded responses keyed by the method and the url, the request that is not recorded is responded with
+/// `404 Not Found`, it runs without the network, so it does not support the control
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct MockFetcher {
+    responses: HashMap<(Method, String), HttpResponse>,
+}
+impl MockFetcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, method: Method, url: &str, response: HttpResponse) -> Self {
+        self.responses.insert((method, url.to_string()), response);
+        self
+    }
+
+    pub fn with_get(self, url: &str, response: HttpResponse) -> Self {
+        self.with(Method::Get, url, response)
+    }
+
+    /// `200 OK` with the JSON body
+    pub fn with_json<T: Serialize>(self, url: &str, body: &T) -> Self {
+        let body = serde_json::to_vec(body).expect("recorded response should be serializable");
+        self.with_get(url, HttpResponse::new(200).with_body(body))
+    }
+}
+impl Fetch for MockFetcher {
+    async fn fetch(&self, request: HttpRequest, _control: Option<WalkControl>) -> Result<HttpResponse> {
+        let HttpRequest { method, url, .. } = request;
+        let response = self.responses.get(&(method, url.to_string())).cloned();
+        Ok(HttpResponse { url: url.to_string(), ..response.unwrap_or_else(|| HttpResponse::new(404)) })
+    }
+}
+
+/// dispatch the fetchers, so that the repositories can be still cloned and compared
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub enum Fetcher {
+    #[default]
+    Browser,
+    #[cfg(not(target_arch = "wasm32"))]
+    Native(NativeFetcher),
+    Mock(Arc<MockFetcher>),
+}
+impl From<MockFetcher> for Fetcher {
+    fn from(mock: MockFetcher) -> Self {
+        Self::Mock(Arc::new(mock))
+    }
+}
+impl Fetch for Fetcher {
+    async fn fetch(&self, request: HttpRequest, control: Option<WalkControl>) -> Result<HttpResponse> {
+        match self {
+            Self::Browser => BrowserFetcher.fetch(request, control).await,
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Native(native) => native.fetch(request, control).await,
+            Self::Mock(mock) => mock.fetch(request, control).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+
+    #[test]
+    fn test_mock_fetcher() {
+        let url = "https://api.github.com/repos/hayas1/tokei-toukei";
+        let fetcher =
+            Fetcher::from(MockFetcher::new().with_json(url, &serde_json::json!({ "default_branch": "main" })));
+
+        let request = HttpRequest::get(Url::parse(url).unwrap()).header("Accept", "application/vnd.github+json");
+        let response = block_on(fetcher.fetch(request, None)).unwrap();
+        assert!(response.ok());
+        assert_eq!(response.json::<serde_json::Value>().unwrap()["default_branch"], "main");
+
+        let request = HttpRequest::get(Url::parse(url).unwrap()).query([("recursive", "true")]);
+        assert_eq!(request.url.as_str(), format!("{url}?recursive=true"));
+        let response = block_on(fetcher.fetch(request, None)).unwrap();
+        assert_eq!((response.status, response.url), (404, format!("{url}?recursive=true")));
+    }
+}
@@ -0,0 +1,73 @@
+use std::path::Path;
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::error::Result;
+
+/// include and exclude paths of the tree by gitignore-style glob patterns
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    paths: Option<Gitignore>,
+    excluded: Option<Gitignore>,
+}
+impl PathFilter {
+    /// empty `paths` means all paths are included
+    pub fn new<P: AsRef<str>, E: AsRef<str>>(paths: &[P], excluded: &[E]) -> Result<Self> {
+        Ok(Self { paths: Self::build(paths)?, excluded: Self::build(excluded)? })
+    }
+
+    fn build<S: AsRef<str>>(patterns: &[S]) -> Result<Option<Gitignore>> {
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+        let mut builder = GitignoreBuilder::new("");
+        for pattern in patterns {
+            builder.add_line(None, pattern.as_ref()).map_err(anyhow::Error::from)?;
+        }
+        Ok(Some(builder.build().map_err(anyhow::Error::from)?))
+    }
+
+    fn matched<A: AsRef<Path>>(gitignore: &Gitignore, path: A, is_dir: bool) -> bool {
+        gitignore.matched_path_or_any_parents(path, is_dir).is_ignore()
+    }
+
+    pub fn is_included<A: AsRef<Path>>(&self, path: A, is_dir: bool) -> bool {
+        let path = path.as_ref();
+        let included = self.paths.as_ref().is_none_or(|paths| Self::matched(paths, path, is_dir));
+        included && !self.is_excluded(path, is_dir)
+    }
+
+    /// directories can be pruned by only `excluded`, because `paths` may match their descendants
+    pub fn is_excluded<A: AsRef<Path>>(&self, path: A, is_dir: bool) -> bool {
+        self.excluded.as_ref().is_some_and(|excluded| Self::matched(excluded, path, is_dir))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_filter() {
+        let filter = PathFilter::new(&["services/billing/**", "/src"], &["*.md", "src/generated"]).unwrap();
+        assert!(filter.is_included("services/billing/main.go", false));
+        assert!(filter.is_included("src/lib.rs", false));
+        assert!(!filter.is_included("services/billing/README.md", false));
+        assert!(!filter.is_included("src/generated/model.rs", false));
+        assert!(!filter.is_included("services/shipping/main.go", false));
+        assert!(!filter.is_included("tests/src/lib.rs", false));
+
+        assert!(!filter.is_excluded("services", true));
+        assert!(filter.is_excluded("src/generated", true));
+    }
+
+    #[test]
+    fn test_empty_path_filter() {
+        let filter = PathFilter::new::<&str, &str>(&[], &[]).unwrap();
+        assert!(filter.is_included("src/lib.rs", false));
+
+        let filter = PathFilter::new(&["/"], &["!src", "tests"]).unwrap();
+        assert!(filter.is_included("src/lib.rs", false));
+        assert!(!filter.is_included("tests/lib.rs", false));
+    }
+}
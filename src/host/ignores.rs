@@ -0,0 +1,136 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{convert::ConvertError, Error, Result},
+    github::models::{ContentsType, SubtreeModel},
+};
+
+/// the ignore files in the tree are disregarded by default, because they are not applied to the files in git
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IgnoreFilesMode {
+    #[default]
+    Disregard,
+    /// the files that are ignored by `.tokeignore`, `.ignore`, or `.gitignore` are not counted, like the tokei CLI
+    Respect,
+}
+impl FromStr for IgnoreFilesMode {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "disregard" => Ok(Self::Disregard),
+            "respect" => Ok(Self::Respect),
+            mode => Err(anyhow::anyhow!(ConvertError::UnknownIgnoreFilesMode(mode.to_string())))?,
+        }
+    }
+}
+
+/// ignore files at each directory level of the tree, which are compiled with gitignore semantics
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreFiles {
+    /// the ignore files of each directory, in the order of their precedence
+    directories: HashMap<PathBuf, Vec<Gitignore>>,
+}
+impl IgnoreFiles {
+    /// in the same directory, the former takes precedence over the latter, as the tokei CLI does
+    pub const NAMES: [&'static str; 3] = [".tokeignore", ".ignore", ".gitignore"];
+
+    /// the ignore files in the tree, they are listed even if their directories are ignored
+    pub fn find(tree: &[SubtreeModel]) -> Vec<SubtreeModel> {
+        let is_ignore_file =
+            |path: &str| Self::NAMES.iter().any(|name| Path::new(path).file_name() == Some(name.as_ref()));
+        tree.iter()
+            .filter(|subtree| subtree.contents_type == ContentsType::Blob && is_ignore_file(&subtree.path))
+            .cloned()
+            .collect()
+    }
+
+    /// the contents are keyed by the path of the ignore files, the lines that cannot be compiled are skipped
+    pub fn new<P: AsRef<Path>>(contents: &[(P, String)]) -> Self {
+        let mut files: Vec<_> = contents
+            .iter()
+            .filter_map(|(path, content)| {
+                let (path, name) = (path.as_ref(), path.as_ref().file_name()?.to_str()?);
+                let precedence = Self::NAMES.iter().position(|&n| n == name)?;
+                Some((path.parent().unwrap_or(Path::new("")), precedence, content))
+            })
+            .collect();
+        files.sort_by_key(|&(_, precedence, _)| precedence);
+
+        let mut directories: HashMap<_, Vec<_>> = HashMap::new();
+        for (directory, _, content) in files {
+            let mut builder = GitignoreBuilder::new(directory);
+            content.lines().for_each(|line| drop(builder.add_line(None, line)));
+            if let Ok(gitignore) = builder.build() {
+                directories.entry(directory.to_path_buf()).or_default().push(gitignore);
+            }
+        }
+        Self { directories }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.directories.is_empty()
+    }
+
+    /// the entry is ignored if any of its ancestor directories is ignored, it cannot be re-included in that case
+    pub fn is_ignored<P: AsRef<Path>>(&self, path: P, is_dir: bool) -> bool {
+        let path = path.as_ref();
+        let mut directories: Vec<_> = path.ancestors().skip(1).filter(|d| !d.as_os_str().is_empty()).collect();
+        directories.reverse();
+        directories.into_iter().any(|directory| self.matched(directory, true)) || self.matched(path, is_dir)
+    }
+
+    /// the ignore files of the deeper directories take precedence over the ones of their ancestors
+    fn matched(&self, path: &Path, is_dir: bool) -> bool {
+        for directory in path.ancestors().skip(1) {
+            for gitignore in self.directories.get(directory).into_iter().flatten() {
+                match gitignore.matched(path, is_dir) {
+                    Match::Ignore(_) => return true,
+                    Match::Whitelist(_) => return false,
+                    Match::None => (),
+                }
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ignore_files() {
+        let ignore_files = IgnoreFiles::new(&[
+            (".gitignore", "target/\n*.log\n!keep.log\n".to_string()),
+            (".tokeignore", "keep.log\n".to_string()),
+            ("web/.gitignore", "/dist\n!important.log\n".to_string()),
+            ("web/.ignore", "*.min.js\n".to_string()),
+        ]);
+        assert!(ignore_files.is_ignored("target/debug/main.rs", false));
+        assert!(ignore_files.is_ignored("web/target/index.js", false));
+        assert!(ignore_files.is_ignored("server.log", false));
+        // `.tokeignore` takes precedence over `.gitignore` in the same directory
+        assert!(ignore_files.is_ignored("keep.log", false));
+        // the deeper directory takes precedence over its ancestors
+        assert!(!ignore_files.is_ignored("web/important.log", false));
+        assert!(ignore_files.is_ignored("web/dist/app.js", false));
+        assert!(!ignore_files.is_ignored("dist/app.js", false));
+        assert!(ignore_files.is_ignored("web/vendor/jquery.min.js", false));
+        assert!(!ignore_files.is_ignored("src/main.rs", false));
+
+        assert!(IgnoreFiles::new::<&str>(&[]).is_empty());
+        assert_eq!("respect".parse::<IgnoreFilesMode>().unwrap(), IgnoreFilesMode::Respect);
+        assert!("unknown".parse::<IgnoreFilesMode>().is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    error::{
+        repository::{InstanceError, UrlParseError},
+        Result,
+    },
+    gitea::repository::GiteaRepository,
+    github::repository::{GitHubRepository, GitHubToken},
+    gitlab::repository::GitLabRepository,
+};
+
+use super::{Repository, RepositoryHost};
+
+/// kind of the API that an instance provides
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum HostKind {
+    /// Gitea and its forks such as Forgejo
+    Gitea,
+    /// GitHub Enterprise Server
+    GitHub,
+    /// self-managed GitLab, or gitlab.com with the token
+    GitLab,
+}
+impl HostKind {
+    pub const ALL: [Self; 3] = [Self::Gitea, Self::GitHub, Self::GitLab];
+
+    /// lowercase name such as `gitlab`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gitea => "gitea",
+            Self::GitHub => "github",
+            Self::GitLab => "gitlab",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Gitea => "Gitea / Forgejo",
+            Self::GitHub => "GitHub Enterprise Server",
+            Self::GitLab => "GitLab",
+        }
+    }
+}
+
+/// hosting service that runs on its own origin, `name` is used as the `:host` segment of the route
+/// `token` is sent only to the instance, currently used by GitHub Enterprise Server and GitLab
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Instance {
+    pub name: String,
+    pub kind: HostKind,
+    pub origin: Url,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<GitHubToken>,
+}
+impl Instance {
+    pub const CODEBERG: &'static str = "https://codeberg.org";
+
+    pub fn new(name: &str, kind: HostKind, origin: Url) -> Self {
+        Self { name: name.to_string(), kind, origin, token: None }
+    }
+
+    pub fn with_token(self, token: Option<GitHubToken>) -> Self {
+        Self { token, ..self }
+    }
+
+    /// instances that are available without registration
+    pub fn builtin() -> Vec<Self> {
+        let codeberg = Url::parse(Self::CODEBERG).expect("origin of codeberg.org should be valid url");
+        vec![Self::new("codeberg", HostKind::Gitea, codeberg)]
+    }
+
+    fn base_segments(&self) -> Vec<&str> {
+        self.origin.path_segments().into_iter().flatten().filter(|s| !s.is_empty()).collect()
+    }
+
+    /// whether the url is under the origin (and the base path) of the instance
+    pub fn contains(&self, url: &Url) -> bool {
+        let segments: Vec<_> = url.path_segments().into_iter().flatten().collect();
+        url.origin() == self.origin.origin() && segments.starts_with(&self.base_segments())
+    }
+
+    /// path segments of the url that follow the base path of the instance
+    pub fn relative_segments(&self, url: &Url) -> Result<Vec<String>> {
+        if !self.contains(url) {
+            Err(anyhow::anyhow!(UrlParseError::InvalidHost))?
+        }
+        let path_segments = url.path_segments().ok_or_else(|| anyhow::anyhow!(UrlParseError::Unspecified))?;
+        let segments = path_segments.skip(self.base_segments().len()).filter(|s| !s.is_empty());
+        Ok(segments.map(|s| s.to_string()).collect())
+    }
+
+    pub fn repository(&self, owner: &str, repo: &str) -> Repository {
+        match self.kind {
+            HostKind::Gitea => Repository::Gitea(GiteaRepository::new(self, owner, repo)),
+            HostKind::GitHub => Repository::GitHub(GitHubRepository::enterprise(self, owner, repo)),
+            HostKind::GitLab => Repository::GitLab(GitLabRepository::instance(self, owner, repo)),
+        }
+    }
+
+    pub fn from_url(&self, url: &Url) -> Result<Repository> {
+        match self.kind {
+            HostKind::Gitea => Ok(Repository::Gitea(GiteaRepository::from_instance_url(self, url)?)),
+            HostKind::GitHub => Ok(Repository::GitHub(GitHubRepository::from_instance_url(self, url)?)),
+            HostKind::GitLab => Ok(Repository::GitLab(GitLabRepository::from_instance_url(self, url)?)),
+        }
+    }
+}
+
+/// instances registered by the user in addition to the builtin ones
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+pub struct Instances {
+    pub registered: Vec<Instance>,
+}
+impl Instances {
+    pub fn new(registered: Vec<Instance>) -> Self {
+        Self { registered }
+    }
+
+    /// the name should be unique, and should not shadow the hosts that are resolved without registration
+    pub fn register(mut self, instance: Instance) -> Result<Self> {
+        let name = &instance.name;
+        if name.is_empty() || name.contains(['/', '.', ' ']) || Repository::new(name, "owner", "repo").is_ok() {
+            Err(anyhow::anyhow!(InstanceError::InvalidName(name.clone())))?
+        } else if self.get(name).is_some() {
+            Err(anyhow::anyhow!(InstanceError::Duplicated(name.clone())))?
+        }
+        self.registered.push(instance);
+        Ok(self)
+    }
+
+    pub fn remove(mut self, name: &str) -> Self {
+        self.registered.retain(|instance| instance.name != name);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Instance> {
+        self.registered.iter().find(|instance| instance.name == name)
+    }
+
+    /// resolve the `:host` segment of the route, registered instances are looked up first
+    pub fn repository(&self, host: &str, owner: &str, repo: &str) -> Result<Repository> {
+        match self.get(host) {
+            Some(instance) => Ok(instance.repository(owner, repo)),
+            None => Repository::new(host, owner, repo),
+        }
+    }
+
+    pub fn from_url(&self, url: &Url) -> Result<Repository> {
+        match self.registered.iter().find(|instance| instance.contains(url)) {
+            Some(instance) => instance.from_url(url),
+            None => Repository::from_url(url),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registered_instance() {
+        let origin = Url::parse("https://git.example.com").unwrap();
+        let instances = Instances::default().register(Instance::new("example", HostKind::Gitea, origin)).unwrap();
+
+        let repository = instances.repository("example", "owner", "repo").unwrap();
+        assert_eq!(repository.to_url().unwrap().as_str(), "https://git.example.com/owner/repo");
+        let url = Url::parse("https://git.example.com/owner/repo/src/branch/main").unwrap();
+        assert_eq!(instances.from_url(&url).unwrap(), repository);
+
+        let codeberg = instances.repository("codeberg", "forgejo", "forgejo").unwrap();
+        assert_eq!(codeberg.to_url().unwrap().as_str(), "https://codeberg.org/forgejo/forgejo");
+        assert!(Instances::default().repository("example", "owner", "repo").is_err());
+    }
+
+    #[test]
+    fn test_enterprise_instance() {
+        let origin = Url::parse("https://git.corp.example").unwrap();
+        let token = Some(GitHubToken::new("ghp_corp"));
+        let instance = Instance::new("corp", HostKind::GitHub, origin).with_token(token.clone());
+        let instances = Instances::default().register(instance).unwrap();
+
+        let url = Url::parse("https://git.corp.example/team/service").unwrap();
+        let Repository::GitHub(repository) = instances.from_url(&url).unwrap() else { panic!("should be GitHub") };
+        assert_eq!(repository.api_endpoint("/").unwrap().as_str(), "https://git.corp.example/api/v3/");
+        assert_eq!(repository.token, token);
+
+        // the token of github.com is not sent to the enterprise server
+        let repository = instances.repository("corp", "team", "service").unwrap().with_token(None);
+        assert!(matches!(repository, Repository::GitHub(GitHubRepository { token: Some(_), .. })));
+    }
+
+    #[test]
+    fn test_gitlab_instance() {
+        let origin = Url::parse("https://git.corp.example").unwrap();
+        let token = Some(GitHubToken::new("glpat_corp"));
+        let instance = Instance::new("corp", HostKind::GitLab, origin).with_token(token.clone());
+        let instances = Instances::default().register(instance).unwrap();
+
+        let url = Url::parse("https://git.corp.example/group/subgroup/project/-/tree/main").unwrap();
+        let Repository::GitLab(repository) = instances.from_url(&url).unwrap() else { panic!("should be GitLab") };
+        assert_eq!((repository.owner.as_str(), &repository.token), ("group/subgroup", &token));
+        assert_eq!(instances.repository("corp", "group/subgroup", "project").unwrap(), Repository::GitLab(repository));
+        assert!(Repository::from_url(&url).is_err());
+    }
+
+    #[test]
+    fn test_register_invalid_name() {
+        let origin = Url::parse("https://git.example.com").unwrap();
+        for name in ["", "github", "codeberg", "gitlab.example.com", "a/b"] {
+            let instance = Instance::new(name, HostKind::Gitea, origin.clone());
+            assert!(Instances::default().register(instance).is_err(), "{name}");
+        }
+        let instances = Instances::default().register(Instance::new("example", HostKind::Gitea, origin.clone()));
+        assert!(instances.unwrap().register(Instance::new("example", HostKind::Gitea, origin)).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use std::{collections::BTreeSet, fmt::Display, path::Path, str::FromStr};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use serde::{Deserialize, Serialize};
+use tokei::{CodeStats, LanguageType};
+
+/// categories of the files by the linguist attributes of `.gitattributes`, which are toggled in the table
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Category {
+    /// `linguist-vendored`
+    Vendored,
+    /// `linguist-generated`
+    Generated,
+    /// `linguist-documentation`
+    Documentation,
+    /// `linguist-language`, the file is counted as the language of the attribute instead of the detected one
+    Overridden,
+}
+impl Display for Category {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Vendored => write!(f, "vendored"),
+            Self::Generated => write!(f, "generated"),
+            Self::Documentation => write!(f, "documentation"),
+            Self::Overridden => write!(f, "overridden language"),
+        }
+    }
+}
+impl Category {
+    pub const ALL: [Self; 4] = [Self::Vendored, Self::Generated, Self::Documentation, Self::Overridden];
+
+    /// like the language statistics of GitHub, the vendored, generated, and documentation files are not counted, and
+    /// the overridden languages are applied
+    pub fn shown_by_default() -> BTreeSet<Self> {
+        BTreeSet::from([Self::Overridden])
+    }
+
+    /// the categories whose visibility is toggled from the default
+    pub fn shown(toggled: &[Self]) -> BTreeSet<Self> {
+        toggled.iter().fold(Self::shown_by_default(), |mut shown, category| {
+            if !shown.remove(category) {
+                shown.insert(*category);
+            }
+            shown
+        })
+    }
+}
+
+/// the state of an attribute, `!attr` makes it unspecified again
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum State {
+    Set,
+    Unset,
+    Value(String),
+}
+impl State {
+    fn is_set(&self) -> bool {
+        match self {
+            Self::Set => true,
+            Self::Unset => false,
+            Self::Value(value) => value != "false",
+        }
+    }
+}
+
+/// the name of an attribute and its state, `None` if it is unspecified
+type Attribute = (String, Option<State>);
+
+/// linguist attributes of a file
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct Linguist {
+    /// vendored, generated, or documentation
+    pub categories: BTreeSet<Category>,
+    pub language: Option<LanguageType>,
+}
+
+/// `.gitattributes` at the root of the tree, the attributes of the later lines take precedence over the earlier ones
+#[derive(Debug, Clone, Default)]
+pub struct GitAttributes {
+    lines: Vec<(Gitignore, Vec<Attribute>)>,
+}
+impl GitAttributes {
+    pub const GITATTRIBUTES: &'static str = ".gitattributes";
+    pub const VENDORED: &'static str = "linguist-vendored";
+    pub const GENERATED: &'static str = "linguist-generated";
+    pub const DOCUMENTATION: &'static str = "linguist-documentation";
+    pub const LANGUAGE: &'static str = "linguist-language";
+
+    pub fn attribute(category: Category) -> &'static str {
+        match category {
+            Category::Vendored => Self::VENDORED,
+            Category::Generated => Self::GENERATED,
+            Category::Documentation => Self::DOCUMENTATION,
+            Category::Overridden => Self::LANGUAGE,
+        }
+    }
+
+    /// the patterns are the same as gitignore except negation, the macros such as `[attr]binary` are not expanded
+    pub fn parse(content: &str) -> Self {
+        let mut lines = Vec::new();
+        for line in content.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let mut words = line.split_whitespace();
+            let Some(pattern) = words.next().filter(|p| !p.starts_with(['!', '['])) else { continue };
+            let mut builder = GitignoreBuilder::new("");
+            let Ok(gitignore) = builder.add_line(None, pattern).and_then(|builder| builder.build()) else { continue };
+            let attributes = words
+                .map(|word| match word.split_once('=') {
+                    Some((name, value)) => (name.to_string(), Some(State::Value(value.to_string()))),
+                    None => match (word.strip_prefix('-'), word.strip_prefix('!')) {
+                        (Some(name), _) => (name.to_string(), Some(State::Unset)),
+                        (_, Some(name)) => (name.to_string(), None),
+                        _ => (word.to_string(), Some(State::Set)),
+                    },
+                })
+                .collect();
+            lines.push((gitignore, attributes));
+        }
+        Self { lines }
+    }
+
+    fn state<P: AsRef<Path>>(&self, path: P, name: &str) -> Option<&State> {
+        let matched = self.lines.iter().filter(|(gitignore, _)| gitignore.matched(path.as_ref(), false).is_ignore());
+        let states = matched.flat_map(|(_, attributes)| attributes.iter().filter(|(n, _)| n == name));
+        states.last().and_then(|(_, state)| state.as_ref())
+    }
+
+    /// the language is given by its name, in which hyphens may be used instead of spaces such as `Protocol-Buffers`
+    pub fn linguist<P: AsRef<Path>>(&self, path: P) -> Linguist {
+        let path = path.as_ref();
+        let attributes = [
+            (Self::VENDORED, Category::Vendored),
+            (Self::GENERATED, Category::Generated),
+            (Self::DOCUMENTATION, Category::Documentation),
+        ];
+        let categories = attributes
+            .into_iter()
+            .filter(|(name, _)| self.state(path, name).is_some_and(State::is_set))
+            .map(|(_, category)| category)
+            .collect();
+        let language = match self.state(path, Self::LANGUAGE) {
+            Some(State::Value(name)) => {
+                LanguageType::from_str(name).or_else(|_| LanguageType::from_str(&name.replace('-', " "))).ok()
+            }
+            _ => None,
+        };
+        Linguist { categories, language }
+    }
+}
+
+/// file that has any linguist attribute, it is counted in the languages of the statistics as GitHub does, and kept
+/// with both languages so that it can be counted again when the categories are toggled
+#[derive(Debug, Clone, PartialEq)]
+pub struct Classified {
+    pub path: String,
+    pub categories: BTreeSet<Category>,
+    /// the language detected by the path or the shebang
+    pub detected: Option<(LanguageType, CodeStats)>,
+    /// the language of `linguist-language`, only if it differs from the detected one
+    pub overridden: Option<(LanguageType, CodeStats)>,
+}
+impl Classified {
+    pub fn new(
+        path: &str,
+        categories: BTreeSet<Category>,
+        detected: Option<(LanguageType, CodeStats)>,
+        overridden: Option<(LanguageType, CodeStats)>,
+    ) -> Self {
+        let overridden = overridden.filter(|(o, _)| detected.as_ref().map(|(d, _)| d) != Some(o));
+        Self { path: path.to_string(), categories, detected, overridden }
+    }
+
+    pub fn is_classified(&self) -> bool {
+        !self.categories.is_empty() || self.overridden.is_some()
+    }
+
+    pub fn contains(&self, category: Category) -> bool {
+        match category {
+            Category::Overridden => self.overridden.is_some(),
+            category => self.categories.contains(&category),
+        }
+    }
+
+    /// the language and the stats that the file is counted as, `None` if it is not counted
+    pub fn counted(&self, shown: &BTreeSet<Category>) -> Option<&(LanguageType, CodeStats)> {
+        if !self.categories.is_subset(shown) {
+            return None;
+        }
+        match shown.contains(&Category::Overridden) {
+            true => self.overridden.as_ref().or(self.detected.as_ref()),
+            false => self.detected.as_ref(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gitattributes() {
+        let attributes = GitAttributes::parse(
+            r#"
+            # generated code
+            *.pb.go linguist-generated=true
+            api/legacy.pb.go -linguist-generated
+            vendor/** linguist-vendored
+            vendor/internal/** !linguist-vendored
+            docs/** linguist-documentation
+            *.inc linguist-language=C++
+            *.proto linguist-language=Protocol-Buffers
+            !negation linguist-vendored
+            "#,
+        );
+        let linguist = |path: &str| attributes.linguist(path);
+        assert_eq!(linguist("api/user.pb.go").categories, BTreeSet::from([Category::Generated]));
+        assert!(linguist("api/legacy.pb.go").categories.is_empty());
+        assert_eq!(linguist("vendor/lib/a.go").categories, BTreeSet::from([Category::Vendored]));
+        assert!(linguist("vendor/internal/a.go").categories.is_empty());
+        assert_eq!(linguist("docs/guide/index.md").categories, BTreeSet::from([Category::Documentation]));
+        assert_eq!(linguist("src/table.inc").language, Some(LanguageType::Cpp));
+        assert_eq!(linguist("api/user.proto").language, Some(LanguageType::Protobuf));
+        assert_eq!(linguist("src/main.go"), Linguist::default());
+    }
+
+    #[test]
+    fn test_counted() {
+        let config = tokei::Config::default();
+        let parse = |language_type: LanguageType| (language_type, language_type.parse_from_slice(b"int x;\n", &config));
+        let detected = Some(parse(LanguageType::Go));
+        let generated = Classified::new("a.pb.go", BTreeSet::from([Category::Generated]), detected.clone(), None);
+        assert_eq!(generated.counted(&Category::shown_by_default()), None);
+        assert_eq!(generated.counted(&Category::shown(&[Category::Generated])), detected.as_ref());
+
+        let overridden = Some(parse(LanguageType::Cpp));
+        let inc = Classified::new("table.inc", BTreeSet::new(), detected.clone(), overridden.clone());
+        assert_eq!(inc.counted(&Category::shown_by_default()), overridden.as_ref());
+        assert_eq!(inc.counted(&Category::shown(&[Category::Overridden])), detected.as_ref());
+
+        assert!(!Classified::new("main.go", BTreeSet::new(), detected.clone(), detected).is_classified());
+    }
+}
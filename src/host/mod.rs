@@ -0,0 +1,358 @@
+pub mod cache;
+pub mod content;
+pub mod control;
+pub mod fetch;
+pub mod filter;
+pub mod ignores;
+pub mod instance;
+pub mod linguist;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod pool;
+pub mod preflight;
+pub mod statistics;
+pub mod submodule;
+pub mod symlink;
+
+use std::{fmt::Debug, path::Path};
+
+use futures::stream::{self, LocalBoxStream, StreamExt};
+use url::Url;
+
+use crate::{
+    error::{repository::UrlParseError, Result},
+    gitea::repository::GiteaRepository,
+    github::{
+        blob::GitHubBlob,
+        models::{ContentsType, SubtreeModel, TreesModel},
+        repository::{GitHubRepository, GitHubToken},
+    },
+    gitlab::repository::GitLabRepository,
+};
+
+use self::{
+    control::WalkControl,
+    fetch::Fetcher,
+    filter::PathFilter,
+    instance::{HostKind, Instance},
+    statistics::{Statistics, WalkOptions},
+};
+
+/// the commit that a reference (branch, tag, or short SHA) is resolved into
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Commit {
+    pub reference: String,
+    pub sha: String,
+    pub message: String,
+}
+
+/// warn in the console of the browser, or in the standard error of the command line
+pub fn warn(message: &str) {
+    #[cfg(target_arch = "wasm32")]
+    gloo::console::warn!(message);
+    #[cfg(not(target_arch = "wasm32"))]
+    eprintln!("warning: {message}");
+}
+
+/// repository hosting service, the tree and the files are represented by the models of GitHub
+#[allow(async_fn_in_trait)] // futures run on the single thread of wasm, so `Send` is not required
+pub trait RepositoryHost: Debug + Clone + PartialEq {
+    /// `:host` segment of the route
+    fn host(&self) -> String;
+
+    fn owner(&self) -> String;
+
+    fn repo(&self) -> String;
+
+    fn from_url(url: &Url) -> Result<Self>;
+
+    fn to_url(&self) -> Result<Url>;
+
+    fn tree_url(&self, sha: &str) -> Result<Url>;
+
+    async fn default_branch(&self) -> Result<String>;
+
+    /// `reference` can be a branch, a tag, or a (short) commit SHA
+    async fn commit(&self, reference: &str) -> Result<Commit>;
+
+    /// resolve `reference` into the commit, or the head of the default branch if `reference` is not given
+    async fn resolve(&self, reference: Option<&str>) -> Result<Commit> {
+        match reference {
+            Some(reference) => self.commit(reference).await,
+            None => self.commit(&self.default_branch().await?).await,
+        }
+    }
+
+    /// list the entire tree of the commit, `truncated` of the result means that it may be incomplete
+    async fn tree(&self, sha: &str, filter: &PathFilter) -> Result<TreesModel>;
+
+    async fn raw(&self, sha: &str, path: &Path) -> Result<Vec<u8>>;
+
+    /// fetch a file of the tree by itself with the token, for the few files that are read before the walk
+    async fn file(&self, sha: &str, subtree: &SubtreeModel) -> Result<Vec<u8>> {
+        self.raw(sha, Path::new(&subtree.path)).await
+    }
+
+    /// fetch the files one by one, never through an archive that may be much larger than them
+    fn files<'a>(&'a self, sha: &'a str, files: Vec<SubtreeModel>) -> LocalBoxStream<'a, Result<GitHubBlob>> {
+        stream::iter(files)
+            .map(move |subtree| async move {
+                Ok(GitHubBlob::new(subtree.path.clone().into(), self.file(sha, &subtree).await?))
+            })
+            .buffered(32)
+            .boxed_local()
+    }
+
+    /// fetch the blobs of the tree that are included by the filter
+    async fn walk<'a>(
+        &'a self,
+        sha: &'a str,
+        tree: Vec<SubtreeModel>,
+        filter: &PathFilter,
+    ) -> LocalBoxStream<'a, Result<GitHubBlob>> {
+        let paths: Vec<_> = tree
+            .into_iter()
+            .filter(|subtree| subtree.contents_type == ContentsType::Blob && filter.is_included(&subtree.path, false))
+            .map(|subtree| subtree.path)
+            .collect();
+        stream::iter(paths)
+            .map(move |path| async move { Ok(GitHubBlob::new(path.clone().into(), self.raw(sha, path.as_ref()).await?)) })
+            .buffered(32)
+            .boxed_local()
+    }
+}
+
+/// dispatch the `:host` segment of the route into the providers
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Repository {
+    GitHub(GitHubRepository),
+    GitLab(GitLabRepository),
+    Gitea(GiteaRepository),
+}
+impl Repository {
+    pub fn new(host: &str, owner: &str, repo: &str) -> Result<Self> {
+        match host {
+            "github" => Ok(Self::GitHub(GitHubRepository::new(owner, repo))),
+            "gitlab" => Ok(Self::GitLab(GitLabRepository::new(owner, repo))),
+            name => match Instance::builtin().into_iter().find(|instance| instance.name == name) {
+                Some(instance) => Ok(instance.repository(owner, repo)),
+                None => Err(anyhow::anyhow!(UrlParseError::UnsupportedHost(host.to_string())))?,
+            },
+        }
+    }
+
+    /// the token is used only for github.com, enterprise servers use the token of their instance
+    pub fn with_token(self, token: Option<GitHubToken>) -> Self {
+        match self {
+            Self::GitHub(repository) if repository.is_github_com() => Self::GitHub(repository.with_token(token)),
+            Self::GitHub(repository) => Self::GitHub(repository),
+            Self::GitLab(repository) => Self::GitLab(repository),
+            Self::Gitea(repository) => Self::Gitea(repository),
+        }
+    }
+
+    /// the API responses are cached with their ETags, only GitHub returns them
+    pub fn with_cache(self, cache: bool) -> Self {
+        match self {
+            Self::GitHub(repository) => Self::GitHub(repository.with_cache(cache)),
+            repository @ (Self::GitLab(_) | Self::Gitea(_)) => repository,
+        }
+    }
+
+    /// the requests of the repository are aborted or paused by the control
+    pub fn with_control(self, control: Option<WalkControl>) -> Self {
+        match self {
+            Self::GitHub(repository) => Self::GitHub(repository.with_control(control)),
+            Self::GitLab(repository) => Self::GitLab(repository.with_control(control)),
+            Self::Gitea(repository) => Self::Gitea(repository.with_control(control)),
+        }
+    }
+
+    pub fn control(&self) -> Option<WalkControl> {
+        match self {
+            Self::GitHub(repository) => repository.control,
+            Self::GitLab(repository) => repository.control,
+            Self::Gitea(repository) => repository.control,
+        }
+    }
+
+    /// the requests of the repository are sent by the fetcher, such as the mock of the tests
+    pub fn with_fetcher(self, fetcher: Fetcher) -> Self {
+        match self {
+            Self::GitHub(repository) => Self::GitHub(repository.with_fetcher(fetcher)),
+            Self::GitLab(repository) => Self::GitLab(repository.with_fetcher(fetcher)),
+            Self::Gitea(repository) => Self::Gitea(repository.with_fetcher(fetcher)),
+        }
+    }
+
+    pub fn fetcher(&self) -> Fetcher {
+        match self {
+            Self::GitHub(repository) => repository.fetcher.clone(),
+            Self::GitLab(repository) => repository.fetcher.clone(),
+            Self::Gitea(repository) => repository.fetcher.clone(),
+        }
+    }
+
+    /// another repository on the same host, which inherits the origins, the token, the control, and the fetcher
+    pub fn sibling(&self, url: &Url) -> Result<Self> {
+        let sibling = match self {
+            Self::GitHub(repository) if repository.is_github_com() => {
+                GitHubRepository::from_url(url).map(|GitHubRepository { owner, repo, .. }| {
+                    Self::GitHub(GitHubRepository { owner, repo, ..repository.clone() })
+                })
+            }
+            Self::GitHub(repository) => {
+                let instance = Instance::new(&repository.name, HostKind::GitHub, repository.origins.origin.clone());
+                let GitHubRepository { owner, repo, .. } = GitHubRepository::from_instance_url(&instance, url)?;
+                Ok(Self::GitHub(GitHubRepository { owner, repo, ..repository.clone() }))
+            }
+            Self::GitLab(repository) => {
+                let instance = Instance::new(&repository.name, HostKind::GitLab, repository.origin.clone());
+                let GitLabRepository { owner, repo, .. } = GitLabRepository::from_instance_url(&instance, url)?;
+                Ok(Self::GitLab(GitLabRepository { owner, repo, ..repository.clone() }))
+            }
+            Self::Gitea(repository) => {
+                let instance = Instance::new(&repository.name, HostKind::Gitea, repository.origin.clone());
+                GiteaRepository::from_instance_url(&instance, url).map(Self::Gitea)
+            }
+        };
+        sibling.map(|sibling| sibling.with_control(self.control()).with_fetcher(self.fetcher()))
+    }
+
+    pub async fn get_statistics(
+        &self,
+        sha: Option<&str>,
+        filter: &PathFilter,
+        config: &tokei::Config,
+        options: WalkOptions,
+    ) -> Result<Statistics> {
+        Statistics::get(self.clone(), sha, filter, config, options).await // TODO lifetime
+    }
+}
+impl RepositoryHost for Repository {
+    fn host(&self) -> String {
+        match self {
+            Self::GitHub(repository) => repository.host(),
+            Self::GitLab(repository) => repository.host(),
+            Self::Gitea(repository) => repository.host(),
+        }
+    }
+
+    fn owner(&self) -> String {
+        match self {
+            Self::GitHub(repository) => repository.owner(),
+            Self::GitLab(repository) => repository.owner(),
+            Self::Gitea(repository) => repository.owner(),
+        }
+    }
+
+    fn repo(&self) -> String {
+        match self {
+            Self::GitHub(repository) => repository.repo(),
+            Self::GitLab(repository) => repository.repo(),
+            Self::Gitea(repository) => repository.repo(),
+        }
+    }
+
+    fn from_url(url: &Url) -> Result<Self> {
+        GitHubRepository::from_url(url)
+            .map(Self::GitHub)
+            .or_else(|_| GitLabRepository::from_url(url).map(Self::GitLab))
+            .or_else(|_| GiteaRepository::from_url(url).map(Self::Gitea))
+    }
+
+    fn to_url(&self) -> Result<Url> {
+        match self {
+            Self::GitHub(repository) => repository.to_url(),
+            Self::GitLab(repository) => repository.to_url(),
+            Self::Gitea(repository) => repository.to_url(),
+        }
+    }
+
+    fn tree_url(&self, sha: &str) -> Result<Url> {
+        match self {
+            Self::GitHub(repository) => repository.tree_url(sha),
+            Self::GitLab(repository) => repository.tree_url(sha),
+            Self::Gitea(repository) => repository.tree_url(sha),
+        }
+    }
+
+    async fn default_branch(&self) -> Result<String> {
+        match self {
+            Self::GitHub(repository) => repository.default_branch().await,
+            Self::GitLab(repository) => repository.default_branch().await,
+            Self::Gitea(repository) => repository.default_branch().await,
+        }
+    }
+
+    async fn commit(&self, reference: &str) -> Result<Commit> {
+        match self {
+            Self::GitHub(repository) => repository.commit(reference).await,
+            Self::GitLab(repository) => repository.commit(reference).await,
+            Self::Gitea(repository) => repository.commit(reference).await,
+        }
+    }
+
+    async fn tree(&self, sha: &str, filter: &PathFilter) -> Result<TreesModel> {
+        match self {
+            Self::GitHub(repository) => repository.tree(sha, filter).await,
+            Self::GitLab(repository) => repository.tree(sha, filter).await,
+            Self::Gitea(repository) => repository.tree(sha, filter).await,
+        }
+    }
+
+    async fn raw(&self, sha: &str, path: &Path) -> Result<Vec<u8>> {
+        match self {
+            Self::GitHub(repository) => RepositoryHost::raw(repository, sha, path).await,
+            Self::GitLab(repository) => RepositoryHost::raw(repository, sha, path).await,
+            Self::Gitea(repository) => RepositoryHost::raw(repository, sha, path).await,
+        }
+    }
+
+    async fn file(&self, sha: &str, subtree: &SubtreeModel) -> Result<Vec<u8>> {
+        match self {
+            Self::GitHub(repository) => repository.file(sha, subtree).await,
+            Self::GitLab(repository) => repository.file(sha, subtree).await,
+            Self::Gitea(repository) => repository.file(sha, subtree).await,
+        }
+    }
+
+    async fn walk<'a>(
+        &'a self,
+        sha: &'a str,
+        tree: Vec<SubtreeModel>,
+        filter: &PathFilter,
+    ) -> LocalBoxStream<'a, Result<GitHubBlob>> {
+        match self {
+            Self::GitHub(repository) => repository.walk(sha, tree, filter).await,
+            Self::GitLab(repository) => repository.walk(sha, tree, filter).await,
+            Self::Gitea(repository) => repository.walk(sha, tree, filter).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dispatch_host() {
+        let repository = Repository::new("github", "hayas1", "tokei-toukei").unwrap();
+        assert_eq!(repository, Repository::GitHub(GitHubRepository::new("hayas1", "tokei-toukei")));
+        assert_eq!(repository.host(), "github");
+
+        let url = Url::parse("https://github.com/hayas1/tokei-toukei").unwrap();
+        assert_eq!(Repository::from_url(&url).unwrap(), repository);
+
+        let url = Url::parse("https://gitlab.com/group/subgroup/project").unwrap();
+        let repository = Repository::from_url(&url).unwrap();
+        assert_eq!(repository, Repository::GitLab(GitLabRepository::new("group/subgroup", "project")));
+        assert_eq!(Repository::new(&repository.host(), "group/subgroup", "project").unwrap(), repository);
+
+        let url = Url::parse("https://codeberg.org/forgejo/forgejo").unwrap();
+        let repository = Repository::from_url(&url).unwrap();
+        assert_eq!(repository.host(), "codeberg");
+        assert_eq!(Repository::new("codeberg", "forgejo", "forgejo").unwrap(), repository);
+
+        assert!(Repository::new("unknown", "hayas1", "tokei-toukei").is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    sync::{
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use futures::channel::oneshot;
+
+use crate::error::{repository::AbortError, Result};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// fixed number of threads that run the blocking jobs, the jobs beyond the threads wait in the queue
+#[derive(Debug, Clone)]
+pub struct WorkerPool {
+    sender: Sender<Job>,
+}
+impl WorkerPool {
+    /// at least one thread is spawned, the threads are alive as long as the pool is
+    pub fn new(workers: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..workers.max(1) {
+            let receiver = receiver.clone();
+            thread::spawn(move || loop {
+                // the lock is released before the job runs, so that the other threads can take the next one
+                let job = match receiver.lock() {
+                    Ok(receiver) => receiver.recv(),
+                    Err(_) => break,
+                };
+                match job {
+                    Ok(job) => job(),
+                    Err(_) => break,
+                }
+            });
+        }
+        Self { sender }
+    }
+
+    /// run the job on the pool without waiting for it
+    pub fn execute<F: FnOnce() + Send + 'static>(&self, job: F) {
+        // the threads are never stopped while the sender is alive, so the job is always received
+        self.sender.send(Box::new(job)).unwrap_or_default();
+    }
+
+    /// run the blocking job on the pool, and wait for its result without blocking the executor
+    pub async fn spawn<T: Send + 'static, F: FnOnce() -> Result<T> + Send + 'static>(&self, job: F) -> Result<T> {
+        let (sender, receiver) = oneshot::channel();
+        self.execute(move || sender.send(job()).unwrap_or_default());
+        receiver.await.map_err(|_| anyhow::anyhow!(AbortError::Aborted))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::{executor::block_on, stream, StreamExt};
+
+    use super::*;
+
+    #[test]
+    fn test_worker_pool() {
+        let pool = WorkerPool::new(4);
+        let (running, max) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let jobs = (0..8).map(|i| {
+            let (pool, running, max) = (pool.clone(), running.clone(), max.clone());
+            async move {
+                pool.spawn(move || {
+                    max.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                    thread::sleep(std::time::Duration::from_millis(50));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                    Ok(i)
+                })
+                .await
+            }
+        });
+        let results: Vec<_> = block_on(stream::iter(jobs).buffered(8).collect());
+        assert_eq!(results.into_iter().map(Result::unwrap).collect::<Vec<_>>(), (0..8).collect::<Vec<_>>());
+        // the jobs run concurrently, but not more than the threads of the pool
+        assert!((2..=4).contains(&max.load(Ordering::SeqCst)));
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use tokei::LanguageType;
+
+use crate::{
+    error::Result,
+    github::models::{ContentsType, FileMode, SubtreeModel, TreesModel},
+};
+
+use super::{filter::PathFilter, Commit, Repository, RepositoryHost};
+
+/// the commit and its tree, which are listed before any blob is fetched
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Preflight {
+    pub repository: Repository,
+    pub commit: Commit,
+    /// the tree may be incomplete even if it is listed level by level
+    pub truncated: bool,
+    pub tree: Vec<SubtreeModel>,
+}
+impl Preflight {
+    pub async fn get(repository: Repository, sha: Option<&str>, filter: &PathFilter) -> Result<Self> {
+        let commit = repository.resolve(sha).await?;
+        let TreesModel { tree, truncated, .. } = repository.tree(&commit.sha, filter).await?;
+        Ok(Self { repository, commit, truncated, tree })
+    }
+
+    /// estimate the files to be fetched by the size in the tree, the symlinks are not followed, and the files whose
+    /// sizes are not in the tree, such as the ones of GitLab, are not skipped by the max file size
+    pub fn estimate(&self, filter: &PathFilter, max_file_size: Option<u64>, config: &tokei::Config) -> Estimate {
+        let mut estimate = Estimate::default();
+        let files = self.tree.iter().filter(|subtree| {
+            subtree.contents_type == ContentsType::Blob
+                && matches!(subtree.file_mode(), FileMode::Regular | FileMode::Executable)
+                && filter.is_included(&subtree.path, false)
+        });
+        for subtree in files {
+            if subtree.size.zip(max_file_size).is_some_and(|(size, max)| size > max) {
+                estimate.oversized += 1;
+                continue;
+            }
+            estimate.total.add(subtree.size);
+            if let Some(language_type) = LanguageType::from_path(&subtree.path, config) {
+                estimate.languages.entry(language_type).or_default().add(subtree.size);
+            }
+        }
+        estimate
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub struct Volume {
+    pub files: usize,
+    pub bytes: u64,
+    /// files whose sizes are unknown, which are not included in `bytes`
+    pub unknown: usize,
+}
+impl Volume {
+    pub fn add(&mut self, size: Option<u64>) {
+        self.files += 1;
+        match size {
+            Some(size) => self.bytes += size,
+            None => self.unknown += 1,
+        }
+    }
+}
+
+/// `total` includes the files whose language is not recognized, because they are also fetched
+#[derive(Debug, Clone, Eq, PartialEq, Default)]
+pub struct Estimate {
+    pub total: Volume,
+    pub languages: BTreeMap<LanguageType, Volume>,
+    /// files over the max file size, which will be skipped
+    pub oversized: usize,
+}
+impl Estimate {
+    /// the files whose sizes are unknown are assumed to be this size, so that their number is compared instead
+    pub const UNKNOWN_FILE_BYTES: u64 = 16 * 1024;
+
+    pub fn exceeds(&self, threshold: u64) -> bool {
+        self.total.bytes + self.total.unknown as u64 * Self::UNKNOWN_FILE_BYTES > threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{github::repository::GitHubRepository, gitlab::repository::GitLabRepository};
+
+    fn blob(path: &str, mode: &str, size: u64) -> SubtreeModel {
+        let (path, mode, sha) = (path.to_string(), mode.to_string(), path.to_string());
+        SubtreeModel { path, mode, contents_type: ContentsType::Blob, sha, size: Some(size), url: None }
+    }
+
+    #[test]
+    fn test_estimate() {
+        let preflight = Preflight {
+            repository: Repository::GitHub(GitHubRepository::new("hayas1", "tokei-toukei")),
+            commit: Commit { reference: "main".into(), sha: "1c2569b".into(), message: "".into() },
+            truncated: false,
+            tree: vec![
+                blob("src/main.rs", "100644", 300),
+                blob("src/lib.rs", "100644", 700),
+                blob("assets/logo.png", "100644", 5000),
+                blob("data/huge.json", "100644", 50_000_000),
+                blob("README.md", "120000", 20),
+            ],
+        };
+        let (filter, config) = (PathFilter::default(), tokei::Config::default());
+
+        let estimate = preflight.estimate(&filter, None, &config);
+        assert_eq!(estimate.total, Volume { files: 4, bytes: 50_006_000, unknown: 0 });
+        assert_eq!(estimate.languages[&LanguageType::Rust], Volume { files: 2, bytes: 1000, unknown: 0 });
+        assert!(estimate.exceeds(10_000_000));
+
+        let estimate = preflight.estimate(&filter, Some(1_000_000), &config);
+        assert_eq!((estimate.total, estimate.oversized), (Volume { files: 3, bytes: 6000, unknown: 0 }, 1));
+        assert!(!estimate.exceeds(10_000_000));
+    }
+
+    #[test]
+    fn test_estimate_unknown_sizes() {
+        // the trees of GitLab have no sizes
+        let unknown_blob = |path: &str| SubtreeModel { size: None, ..blob(path, "100644", 0) };
+        let preflight = Preflight {
+            repository: Repository::GitLab(GitLabRepository::new("group", "project")),
+            commit: Commit { reference: "main".into(), sha: "1c2569b".into(), message: "".into() },
+            truncated: false,
+            tree: (0..1000).map(|i| unknown_blob(&format!("src/{i}.rs"))).collect(),
+        };
+        let (filter, config) = (PathFilter::default(), tokei::Config::default());
+
+        let estimate = preflight.estimate(&filter, Some(1), &config);
+        assert_eq!((estimate.total, estimate.oversized), (Volume { files: 1000, bytes: 0, unknown: 1000 }, 0));
+        assert!(estimate.exceeds(10_000_000));
+        assert!(!estimate.exceeds(100_000_000));
+    }
+}
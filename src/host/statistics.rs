@@ -0,0 +1,735 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use futures::{pin_mut, StreamExt};
+use tokei::{Language, LanguageType, Languages, Report, Sort};
+
+use crate::error::{
+    repository::{AbortError, RateLimitError},
+    Result,
+};
+
+use crate::github::models::{ContentsType, FileMode, SubtreeModel};
+
+use super::{
+    cache::{Cache, CachedBlob, Lookup},
+    content::Content,
+    filter::PathFilter,
+    ignores::{IgnoreFiles, IgnoreFilesMode},
+    linguist::{Category, Classified, GitAttributes},
+    preflight::Preflight,
+    submodule::{Submodule, SubmoduleMode},
+    symlink::{SymlinkMode, SymlinkResolver},
+    warn, Commit, Repository, RepositoryHost,
+};
+
+/// options of walking the tree, which are given by the query parameters and the settings
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub struct WalkOptions {
+    pub submodules: Option<SubmoduleMode>,
+    pub symlinks: SymlinkMode,
+    pub ignore_files: IgnoreFilesMode,
+    /// files over this size in bytes are skipped without being fetched
+    pub max_file_size: Option<u64>,
+    /// the blobs are looked up in the persistent cache by their SHA, and the fetched ones are stored into it
+    pub cache: bool,
+}
+
+/// entry of the tree that is not counted
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Skipped {
+    pub path: String,
+    pub reason: SkipReason,
+}
+impl Skipped {
+    pub fn new(path: &str, reason: SkipReason) -> Self {
+        Self { path: path.to_string(), reason }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SkipReason {
+    Symlink,
+    /// the target does not exist in the repository, or it is outside the repository
+    DanglingSymlink,
+    /// the chain of the symlinks is too deep
+    UnresolvedSymlink,
+    /// the target is counted by itself or by another symlink
+    DuplicateTarget,
+    UnknownMode,
+    /// over the max file size
+    TooLarge,
+    /// NUL bytes or a magic number of binary formats is found
+    Binary,
+    /// `.tokeignore`, `.ignore`, or `.gitignore` in the tree matches the path
+    Ignored,
+}
+impl Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Symlink => write!(f, "symlink"),
+            Self::DanglingSymlink => write!(f, "symlink to outside of the repository"),
+            Self::UnresolvedSymlink => write!(f, "unresolved symlink"),
+            Self::DuplicateTarget => write!(f, "symlink to already counted files"),
+            Self::UnknownMode => write!(f, "unknown file mode"),
+            Self::TooLarge => write!(f, "over the max file size"),
+            Self::Binary => write!(f, "binary"),
+            Self::Ignored => write!(f, "ignored by the ignore files"),
+        }
+    }
+}
+
+/// progress of fetching the blobs, which is reported every time a blob is fetched
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub struct Progress {
+    pub done: usize,
+    pub total: usize,
+    pub bytes: u64,
+    /// blobs that could not be fetched, which are not counted
+    pub failures: usize,
+    /// blobs that are counted from the cache without being fetched, which are included in `done`
+    pub cached: usize,
+}
+impl Progress {
+    pub fn new(total: usize) -> Self {
+        Self { total, ..Default::default() }
+    }
+
+    pub fn ratio(&self) -> f64 {
+        match self.total {
+            0 => 1.,
+            total => self.done as f64 / total as f64,
+        }
+    }
+}
+
+/// called with the partial languages, which are not totaled yet
+pub type OnProgress<'a> = dyn FnMut(&Progress, &Languages) + 'a;
+
+/// result of the walk, the binaries and the blobs over the max file size are skipped, and the failures are the blobs
+/// that could not be fetched
+#[derive(Debug, Default)]
+pub struct Walked {
+    pub languages: Languages,
+    pub skipped: Vec<Skipped>,
+    pub failures: Vec<String>,
+    pub classified: Vec<Classified>,
+}
+
+/// where the blobs are looked up and how they are classified while walking the tree
+#[derive(Debug, Clone, Copy)]
+pub struct WalkContext<'a> {
+    pub cache: Option<&'a Cache>,
+    pub attributes: &'a GitAttributes,
+    /// the files whose sizes are not in the tree, such as the ones of GitLab, are skipped by the fetched bytes
+    pub max_file_size: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct Statistics {
+    pub repository: Repository,
+    pub commit: Commit,
+    /// the tree may be incomplete even if it is listed level by level
+    pub truncated: bool,
+    pub languages: Languages,
+    /// files that have any linguist attribute of `.gitattributes`, which are counted in `languages` as GitHub does
+    pub classified: Vec<Classified>,
+    /// walked only if the submodule mode is given
+    pub submodules: Vec<SubmoduleStatistics>,
+    pub skipped: Vec<Skipped>,
+    /// errors of the blobs that could not be fetched
+    pub failures: Vec<String>,
+}
+
+/// row of the table of the languages, which is shared by the web view and the command line
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct LanguageRow {
+    pub language_type: LanguageType,
+    pub files: usize,
+    pub lines: usize,
+    pub code: usize,
+    pub comments: usize,
+    pub blanks: usize,
+}
+impl LanguageRow {
+    pub fn new(language_type: LanguageType, language: &Language) -> Self {
+        let Language { code, comments, blanks, .. } = *language;
+        Self { language_type, files: language.reports.len(), lines: language.lines(), code, comments, blanks }
+    }
+
+    pub fn metric(&self, sort: Sort) -> usize {
+        match sort {
+            Sort::Blanks => self.blanks,
+            Sort::Comments => self.comments,
+            Sort::Code => self.code,
+            Sort::Files => self.files,
+            Sort::Lines => self.lines,
+        }
+    }
+}
+
+/// `statistics` is the error message if the submodule is hosted on an unsupported host or cannot be counted, such as
+/// a private repository or a deleted commit, so that it does not fail the statistics of the superproject
+#[derive(Debug, PartialEq, Eq)]
+pub struct SubmoduleStatistics {
+    pub submodule: Submodule,
+    pub statistics: std::result::Result<Arc<Statistics>, String>,
+}
+impl Eq for Statistics {}
+impl PartialEq for Statistics {
+    // TODO Languages is not Eq, PartialEq
+    fn eq(&self, other: &Self) -> bool {
+        self.repository == other.repository
+            && self.commit == other.commit
+            && self.truncated == other.truncated
+            && *self.languages == *other.languages
+            && self.classified == other.classified
+            && self.submodules == other.submodules
+            && self.skipped == other.skipped
+            && self.failures == other.failures
+    }
+}
+impl Statistics {
+    pub async fn get(
+        repository: Repository,
+        sha: Option<&str>,
+        filter: &PathFilter,
+        config: &tokei::Config,
+        options: WalkOptions,
+    ) -> Result<Self> {
+        let preflight = Preflight::get(repository.with_cache(options.cache), sha, filter).await?;
+        Self::from_preflight(&preflight, filter, config, options).await
+    }
+
+    /// fetch the blobs of the tree that is listed by the pre-flight
+    pub async fn from_preflight(
+        preflight: &Preflight,
+        filter: &PathFilter,
+        config: &tokei::Config,
+        options: WalkOptions,
+    ) -> Result<Self> {
+        Self::from_preflight_with_progress(preflight, filter, config, options, &mut |_, _| ()).await
+    }
+
+    /// the progress is reported while the blobs of the superproject are fetched, not while the submodules are
+    pub async fn from_preflight_with_progress(
+        preflight: &Preflight,
+        filter: &PathFilter,
+        config: &tokei::Config,
+        options: WalkOptions,
+        on_progress: &mut OnProgress<'_>,
+    ) -> Result<Self> {
+        let Preflight { repository, commit, truncated, mut tree } = preflight.clone();
+        let (mut ignored, mut failures) = (Vec::new(), Vec::new());
+        if let IgnoreFilesMode::Respect = options.ignore_files {
+            let ignore_files = Self::ignore_files(&repository, &commit.sha, &tree, &mut failures).await?;
+            let (rest, removed): (Vec<_>, Vec<_>) = tree.into_iter().partition(|subtree| {
+                !ignore_files.is_ignored(&subtree.path, subtree.contents_type == ContentsType::Tree)
+            });
+            let blobs = removed.iter().filter(|subtree| subtree.contents_type == ContentsType::Blob);
+            let included = blobs.filter(|subtree| filter.is_included(&subtree.path, false));
+            ignored.extend(included.map(|subtree| Skipped::new(&subtree.path, SkipReason::Ignored)));
+            tree = rest;
+        }
+        let gitlinks: Vec<_> = tree
+            .iter()
+            .filter(|subtree| subtree.contents_type == ContentsType::Commit && !filter.is_excluded(&subtree.path, true))
+            .cloned()
+            .collect();
+        let (mut files, mut skipped) =
+            Self::classify(&repository, &commit.sha, &tree, filter, options.symlinks, &mut failures).await?;
+        skipped.extend(ignored);
+        if let Some(max_file_size) = options.max_file_size {
+            let (oversized, rest): (Vec<_>, _) =
+                files.into_iter().partition(|file| file.size.is_some_and(|size| size > max_file_size));
+            skipped.extend(oversized.iter().map(|file| Skipped::new(&file.path, SkipReason::TooLarge)));
+            files = rest;
+        }
+        let cache = match options.cache {
+            true => Cache::open().await.inspect_err(|err| warn(&err.to_string())).ok(),
+            false => None,
+        };
+        // the files are counted without the attributes that cannot be fetched, and it is reported as a failure
+        let attributes = match Self::attributes(&repository, &commit.sha, &tree).await {
+            Ok(attributes) => attributes,
+            Err(err) if err.downcast_ref::<RateLimitError>().is_some() => return Err(err),
+            Err(err) if err.downcast_ref::<AbortError>().is_some() => return Err(err),
+            Err(err) => {
+                failures.push(format!("{}: {err}", GitAttributes::GITATTRIBUTES));
+                GitAttributes::default()
+            }
+        };
+        let context =
+            WalkContext { cache: cache.as_ref(), attributes: &attributes, max_file_size: options.max_file_size };
+        // the files are already filtered, and the targets of the symlinks may be out of the filter
+        let walked =
+            Self::get_statistics(&repository, &commit.sha, files, &PathFilter::default(), config, context, on_progress)
+                .await?;
+        let Walked { mut languages, skipped: walk_skipped, failures: walk_failures, mut classified } = walked;
+        skipped.extend(walk_skipped);
+        failures.extend(walk_failures);
+        let submodules = match options.submodules {
+            Some(_) if !gitlinks.is_empty() => {
+                Self::get_submodules(&repository, &commit.sha, &tree, &gitlinks, config, options).await?
+            }
+            _ => Vec::new(),
+        };
+        if let Some(SubmoduleMode::Merged) = options.submodules {
+            Self::merge_submodules(&mut languages, &mut classified, &submodules);
+        }
+        Ok(Self { repository, commit, truncated, languages, classified, submodules, skipped, failures })
+    }
+
+    /// `.gitattributes` at the root of the tree, which is fetched with the token like the other files
+    pub async fn attributes(repository: &Repository, sha: &str, tree: &[SubtreeModel]) -> Result<GitAttributes> {
+        let is_gitattributes =
+            |s: &&SubtreeModel| s.path == GitAttributes::GITATTRIBUTES && s.contents_type == ContentsType::Blob;
+        match tree.iter().find(is_gitattributes) {
+            Some(subtree) => Ok(GitAttributes::parse(&String::from_utf8_lossy(&repository.file(sha, subtree).await?))),
+            None => Ok(GitAttributes::default()),
+        }
+    }
+
+    /// the ignore files at each directory of the tree, they are fetched one by one so that the whole repository is
+    /// not downloaded, and the ones that cannot be fetched are not applied but reported as failures
+    pub async fn ignore_files(
+        repository: &Repository,
+        sha: &str,
+        tree: &[SubtreeModel],
+        failures: &mut Vec<String>,
+    ) -> Result<IgnoreFiles> {
+        let found = IgnoreFiles::find(tree);
+        if found.is_empty() {
+            return Ok(IgnoreFiles::default());
+        }
+        let blobs: Vec<_> = repository.files(sha, found).collect().await;
+        let mut contents = Vec::new();
+        for blob in blobs {
+            match blob {
+                Ok(blob) => contents.push((blob.path, String::from_utf8_lossy(&blob.content).into_owned())),
+                Err(err) if err.downcast_ref::<RateLimitError>().is_some() => return Err(err),
+                Err(err) if err.downcast_ref::<AbortError>().is_some() => return Err(err),
+                Err(err) => failures.push(err.to_string()),
+            }
+        }
+        Ok(IgnoreFiles::new(&contents))
+    }
+
+    /// statistics of the partial languages that are reported as the progress, without submodules and skipped entries
+    pub fn partial(preflight: &Preflight, languages: &Languages) -> Self {
+        let Preflight { repository, commit, truncated, .. } = preflight.clone();
+        let languages = Self::snapshot(languages);
+        Self {
+            repository,
+            commit,
+            truncated,
+            languages,
+            classified: Vec::new(),
+            submodules: Vec::new(),
+            skipped: Vec::new(),
+            failures: Vec::new(),
+        }
+    }
+
+    /// files to be counted, by the mode of the entries of the tree
+    pub async fn classify(
+        repository: &Repository,
+        sha: &str,
+        tree: &[SubtreeModel],
+        filter: &PathFilter,
+        symlinks: SymlinkMode,
+        failures: &mut Vec<String>,
+    ) -> Result<(Vec<SubtreeModel>, Vec<Skipped>)> {
+        let (mut files, mut links, mut skipped) = (Vec::new(), Vec::new(), Vec::new());
+        for subtree in tree.iter().filter(|subtree| subtree.contents_type == ContentsType::Blob) {
+            if !filter.is_included(&subtree.path, false) {
+                continue;
+            }
+            match subtree.file_mode() {
+                FileMode::Regular | FileMode::Executable => files.push(subtree.clone()),
+                FileMode::Symlink => links.push(subtree.clone()),
+                FileMode::Directory | FileMode::Gitlink | FileMode::Unknown => {
+                    skipped.push(Skipped::new(&subtree.path, SkipReason::UnknownMode))
+                }
+            }
+        }
+        match symlinks {
+            SymlinkMode::Skip => skipped.extend(links.iter().map(|link| Skipped::new(&link.path, SkipReason::Symlink))),
+            SymlinkMode::Follow if !links.is_empty() => {
+                // the content of the symlink is the path of the target, the symlink whose target cannot be fetched is
+                // skipped as unresolved
+                let blobs: Vec<_> = repository.files(sha, links.clone()).collect().await;
+                let mut targets = HashMap::new();
+                for blob in blobs {
+                    match blob {
+                        Ok(blob) => {
+                            let target = String::from_utf8_lossy(&blob.content).into_owned();
+                            targets.insert(blob.path.to_string_lossy().into_owned(), target);
+                        }
+                        Err(err) if err.downcast_ref::<RateLimitError>().is_some() => return Err(err),
+                        Err(err) if err.downcast_ref::<AbortError>().is_some() => return Err(err),
+                        Err(err) => failures.push(err.to_string()),
+                    }
+                }
+                let mut counted: HashSet<_> = files.iter().map(|file| file.path.clone()).collect();
+                let links: Vec<_> = links.into_iter().map(|link| link.path).collect();
+                let (followed, unfollowed) = SymlinkResolver::new(tree, &targets).follow(&links, &mut counted);
+                files.extend(followed);
+                skipped.extend(unfollowed);
+            }
+            SymlinkMode::Follow => (),
+        }
+        Ok((files, skipped))
+    }
+
+    /// walk the submodules recursively, the whole tree of each submodule is walked without the filter
+    /// the submodules are walked with the same options as the superproject, the error of each submodule is recorded
+    /// instead of being returned, except the rate limit and the abort that stop the whole walk
+    pub async fn get_submodules(
+        repository: &Repository,
+        sha: &str,
+        tree: &[SubtreeModel],
+        gitlinks: &[SubtreeModel],
+        config: &tokei::Config,
+        options: WalkOptions,
+    ) -> Result<Vec<SubmoduleStatistics>> {
+        let is_gitmodules =
+            |s: &&SubtreeModel| s.path == Submodule::GITMODULES && s.contents_type == ContentsType::Blob;
+        let Some(gitmodules) = tree.iter().find(is_gitmodules) else {
+            return Ok(Vec::new());
+        };
+        let gitmodules = String::from_utf8_lossy(&repository.file(sha, gitmodules).await?).into_owned();
+        let mut submodules = Vec::new();
+        for submodule in Submodule::resolve(&gitmodules, gitlinks) {
+            let statistics = match submodule.repository(repository) {
+                Ok(repository) => {
+                    let filter = PathFilter::default();
+                    let get = Self::get(repository, Some(&submodule.sha), &filter, config, options);
+                    match Box::pin(get).await {
+                        Ok(statistics) => Ok(Arc::new(statistics)),
+                        Err(err) if err.downcast_ref::<RateLimitError>().is_some() => return Err(err),
+                        Err(err) if err.downcast_ref::<AbortError>().is_some() => return Err(err),
+                        Err(err) => Err(err.to_string()),
+                    }
+                }
+                Err(err) => Err(err.to_string()),
+            };
+            submodules.push(SubmoduleStatistics { submodule, statistics });
+        }
+        Ok(submodules)
+    }
+
+    /// merge the reports of the submodules into `languages`, the reports are prefixed by the path of the submodule
+    pub fn merge_submodules(
+        languages: &mut Languages,
+        classified: &mut Vec<Classified>,
+        submodules: &[SubmoduleStatistics],
+    ) {
+        for SubmoduleStatistics { submodule, statistics } in submodules {
+            let Ok(statistics) = statistics else { continue };
+            classified.extend(statistics.classified.iter().map(|file| Classified {
+                path: Path::new(&submodule.path).join(&file.path).to_string_lossy().into_owned(),
+                ..file.clone()
+            }));
+            for (language_type, language) in statistics.languages.iter() {
+                let merged = languages.entry(*language_type).or_default();
+                for report in &language.reports {
+                    let mut report = report.clone();
+                    report.name = Path::new(&submodule.path).join(report.name);
+                    merged.add_report(report);
+                }
+            }
+        }
+        languages.iter_mut().for_each(|(_, language)| language.total());
+    }
+
+    pub async fn get_statistics(
+        repository: &Repository,
+        sha: &str,
+        tree: Vec<SubtreeModel>,
+        filter: &PathFilter,
+        config: &tokei::Config,
+        context: WalkContext<'_>,
+        on_progress: &mut OnProgress<'_>,
+    ) -> Result<Walked> {
+        let mut walked = Self::walk(repository, sha, tree, filter, config, context, on_progress).await?;
+        walked.languages.iter_mut().for_each(|(_, language)| language.total());
+        Ok(walked)
+    }
+
+    /// the rows are in the order of the language types unless `order_by` is given, then in its descending order
+    pub fn rows(&self, order_by: Option<Sort>) -> Vec<LanguageRow> {
+        Self::sorted_rows(&self.languages, order_by)
+    }
+
+    /// the rows of the languages that are counted with the shown categories of the linguist attributes
+    pub fn rows_with(&self, order_by: Option<Sort>, shown: &BTreeSet<Category>) -> Vec<LanguageRow> {
+        Self::sorted_rows(&self.categorized(shown), order_by)
+    }
+
+    fn sorted_rows(languages: &Languages, order_by: Option<Sort>) -> Vec<LanguageRow> {
+        let mut rows: Vec<_> = languages.iter().map(|(&t, language)| LanguageRow::new(t, language)).collect();
+        if let Some(sort) = order_by {
+            rows.sort_by_key(|row| Reverse(row.metric(sort)));
+        }
+        rows
+    }
+
+    /// the classified files are moved between the languages, or added and removed, from the default of GitHub
+    pub fn categorized(&self, shown: &BTreeSet<Category>) -> Languages {
+        let mut languages = Self::as_languages((*self.languages).clone());
+        let default = Category::shown_by_default();
+        for file in &self.classified {
+            let (before, after) = (file.counted(&default), file.counted(shown));
+            if before == after {
+                continue;
+            }
+            if let Some(language) = before.and_then(|(language_type, _)| languages.get_mut(language_type)) {
+                language.reports.retain(|report| report.name != Path::new(&file.path));
+            }
+            if let Some((language_type, stats)) = after {
+                let mut report = Report::new(file.path.clone().into());
+                report += stats.clone();
+                languages.entry(*language_type).or_default().add_report(report);
+            }
+        }
+        languages.retain(|_, language| !language.reports.is_empty());
+        languages.iter_mut().for_each(|(_, language)| language.total());
+        languages
+    }
+
+    /// totaled copy of the partial languages that are reported as the progress
+    pub fn snapshot(languages: &Languages) -> Languages {
+        let mut snapshot = Self::as_languages((**languages).clone());
+        snapshot.iter_mut().for_each(|(_, language)| language.total());
+        snapshot
+    }
+
+    /// `from_previous` is private method
+    /// https://github.com/XAMPPRocky/tokei/blob/v12.1.2/src/language/languages.rs#L46-L61
+    pub fn as_languages(map: BTreeMap<LanguageType, Language>) -> Languages {
+        let mut languages = Languages::new();
+        languages.extend(map);
+        languages
+    }
+
+    /// binary files are not counted but returned as skipped, and the blobs that could not be fetched are returned
+    /// as the failures, only the rate limit aborts the walk
+    pub async fn walk(
+        repository: &Repository,
+        sha: &str,
+        tree: Vec<SubtreeModel>,
+        filter: &PathFilter,
+        config: &tokei::Config,
+        context: WalkContext<'_>,
+        on_progress: &mut OnProgress<'_>,
+    ) -> Result<Walked> {
+        let WalkContext { cache, attributes, max_file_size } = context;
+        let (mut languages, mut skipped, mut failures, mut classified) =
+            (Languages::new(), Vec::new(), Vec::new(), Vec::new());
+        let tree: Vec<_> = tree.into_iter().filter(|subtree| filter.is_included(&subtree.path, false)).collect();
+        let mut progress = Progress::new(tree.len());
+        let executables: HashSet<_> = tree
+            .iter()
+            .filter(|subtree| subtree.file_mode() == FileMode::Executable)
+            .map(|subtree| Path::new(&subtree.path).to_path_buf())
+            .collect();
+        let shas: HashMap<_, _> =
+            tree.iter().map(|subtree| (PathBuf::from(&subtree.path), subtree.sha.clone())).collect();
+
+        let mut cached = match cache {
+            Some(cache) => cache.get_blobs(tree.iter().map(|subtree| &subtree.sha[..])).await.unwrap_or_else(|err| {
+                warn(&err.to_string());
+                HashMap::new()
+            }),
+            None => HashMap::new(),
+        };
+        let (mut hits, mut fetched) = (Vec::new(), Vec::new());
+        for subtree in tree {
+            let (path, executable) = (Path::new(&subtree.path), executables.contains(Path::new(&subtree.path)));
+            let language = attributes.linguist(path).language;
+            // the blob is fetched unless it is cached as both the detected language and the overridden one
+            let hit = cached.get(&subtree.sha).and_then(|blob| {
+                let detected = blob.lookup(path, executable, config)?;
+                let overridden = match language {
+                    Some(language_type) => Some(blob.lookup_as(language_type)?),
+                    None => None,
+                };
+                Some((detected, overridden))
+            });
+            match hit {
+                Some(lookup) => hits.push((subtree, lookup)),
+                None => fetched.push(subtree),
+            }
+        }
+        for (subtree, (detected, overridden)) in hits {
+            (progress.done, progress.cached) = (progress.done + 1, progress.cached + 1);
+            let bytes = cached.get(&subtree.sha).map_or(0, CachedBlob::bytes);
+            progress.bytes += bytes;
+            if max_file_size.is_some_and(|max| bytes > max) {
+                skipped.push(Skipped::new(&subtree.path, SkipReason::TooLarge));
+                continue;
+            }
+            let counted = |lookup| match lookup {
+                Lookup::Counted(language_type, stats) => Some((language_type, stats)),
+                Lookup::Binary | Lookup::Unknown => None,
+            };
+            match detected {
+                Lookup::Binary => skipped.push(Skipped::new(&subtree.path, SkipReason::Binary)),
+                detected => {
+                    let (detected, overridden) = (counted(detected), overridden.and_then(counted));
+                    let file = Classified::new(&subtree.path, BTreeSet::new(), detected, overridden);
+                    Self::count(&mut languages, &mut classified, attributes, file);
+                }
+            }
+        }
+
+        let stream = repository.walk(sha, fetched, filter).await;
+        pin_mut!(stream); // needed for iteration
+        on_progress(&progress, &languages);
+        let mut pending = HashMap::new();
+        let walked = loop {
+            // the stream is not polled while the walk is paused, so that no more requests are sent
+            if let Some(control) = repository.control() {
+                if let Err(err) = control.resumed().await {
+                    break Err(err);
+                }
+            }
+            let Some(value) = stream.next().await else {
+                break Ok(());
+            };
+            progress.done += 1;
+            match value {
+                Ok(blob) if max_file_size.is_some_and(|max| blob.content.len() as u64 > max) => {
+                    progress.bytes += blob.content.len() as u64;
+                    skipped.push(Skipped::new(&blob.path.to_string_lossy(), SkipReason::TooLarge));
+                }
+                Ok(blob) => {
+                    let (bytes, sha) = (blob.content.len() as u64, shas.get(&blob.path).cloned());
+                    progress.bytes += bytes;
+                    let entry = match blob.decode() {
+                        Content::Text { text, .. } => {
+                            let executable = executables.contains(&blob.path);
+                            let path = blob.path.to_string_lossy();
+                            let parse = |language_type: LanguageType| {
+                                (language_type, language_type.parse_from_slice(text.as_bytes(), config))
+                            };
+                            let detected = Self::detect(&blob.path, &text, executable, config).map(parse);
+                            let overridden = attributes.linguist(&blob.path).language.map(parse);
+                            let entry = sha
+                                .as_ref()
+                                .and_then(|sha| pending.remove(sha).or_else(|| cached.remove(sha)))
+                                .unwrap_or_else(|| CachedBlob::text(&text, bytes));
+                            let entry = [&detected, &overridden]
+                                .into_iter()
+                                .flatten()
+                                .fold(entry, |entry, (language_type, stats)| {
+                                    entry.with_stats(*language_type, stats.clone())
+                                });
+                            let file = Classified::new(&path, BTreeSet::new(), detected, overridden);
+                            Self::count(&mut languages, &mut classified, attributes, file);
+                            entry
+                        }
+                        Content::Binary => {
+                            skipped.push(Skipped::new(&blob.path.to_string_lossy(), SkipReason::Binary));
+                            CachedBlob::Binary { bytes }
+                        }
+                    };
+                    if let (Some(sha), Some(_)) = (sha, cache) {
+                        pending.insert(sha, entry);
+                    }
+                }
+                Err(err) if err.downcast_ref::<RateLimitError>().is_some() => break Err(err),
+                Err(err) => {
+                    progress.failures += 1;
+                    failures.push(err.to_string());
+                }
+            }
+            if pending.len() >= Cache::FLUSH_SIZE {
+                Self::flush(cache, &mut pending).await;
+            }
+            on_progress(&progress, &languages);
+        };
+        // the fetched blobs are cached even if the walk is aborted, so that they are not fetched again on retry
+        Self::flush(cache, &mut pending).await;
+
+        walked.map(|()| Walked { languages, skipped, failures, classified })
+    }
+
+    /// the errors of the cache are not fatal, the blobs are just fetched again next time
+    async fn flush(cache: Option<&Cache>, pending: &mut HashMap<String, CachedBlob>) {
+        if let Some(cache) = cache.filter(|_| !pending.is_empty()) {
+            if let Err(err) = cache.put_blobs(pending).await {
+                warn(&err.to_string());
+            }
+        }
+        pending.clear();
+    }
+
+    /// the language by the path, or by the shebang if the file is executable
+    fn detect(path: &Path, text: &str, executable: bool, config: &tokei::Config) -> Option<LanguageType> {
+        LanguageType::from_path(path, config).or_else(|| executable.then(|| Self::from_shebang(text)).flatten())
+    }
+
+    /// the file is counted as GitHub does, files whose language is not recognized are not counted, and the file that
+    /// has any linguist attribute is also kept as classified
+    fn count(
+        languages: &mut Languages,
+        classified: &mut Vec<Classified>,
+        attributes: &GitAttributes,
+        file: Classified,
+    ) {
+        let file = Classified { categories: attributes.linguist(&file.path).categories, ..file };
+        if let Some((language_type, stats)) = file.counted(&Category::shown_by_default()) {
+            let mut report = Report::new(file.path.clone().into());
+            report += stats.clone();
+            languages.entry(*language_type).or_default().add_report(report);
+        }
+        if file.is_classified() {
+            classified.push(file);
+        }
+    }
+
+    /// `LanguageType::from_shebang` reads the file, so the executables are detected by their content
+    pub fn from_shebang(content: &str) -> Option<LanguageType> {
+        let mut words = content.lines().next()?.strip_prefix("#!")?.split_whitespace();
+        let interpreter = match words.next()? {
+            "/usr/bin/env" => words.find(|word| !word.starts_with('-'))?,
+            path => {
+                let shebang = format!("#!{path}");
+                if let Some(&language_type) = LanguageType::list().iter().find(|l| l.shebangs().contains(&&shebang[..]))
+                {
+                    return Some(language_type);
+                }
+                path.rsplit('/').next()?
+            }
+        };
+        LanguageType::from_str(interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.')).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_shebang() {
+        assert_eq!(Statistics::from_shebang("#!/bin/bash\necho hello"), Some(LanguageType::Bash));
+        assert_eq!(Statistics::from_shebang("#!/usr/bin/env python3\n"), Some(LanguageType::Python));
+        assert_eq!(Statistics::from_shebang("#!/usr/bin/env -S ruby -w\n"), Some(LanguageType::Ruby));
+        assert_eq!(Statistics::from_shebang("#!/usr/local/bin/perl\n"), Some(LanguageType::Perl));
+        assert_eq!(Statistics::from_shebang("echo hello"), None);
+    }
+
+    #[test]
+    fn test_progress_ratio() {
+        assert_eq!(Progress::new(0).ratio(), 1.);
+        assert_eq!(Progress { done: 1, ..Progress::new(4) }.ratio(), 0.25);
+    }
+}
@@ -0,0 +1,170 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    error::{convert::ConvertError, repository::UrlParseError, Error, Result},
+    github::models::{ContentsType, SubtreeModel},
+};
+
+use super::{Repository, RepositoryHost};
+
+/// how the statistics of the submodules are reported, submodules are not walked if it is not given
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubmoduleMode {
+    /// the reports of the submodules are merged into the statistics of the superproject
+    Merged,
+    /// the statistics of each submodule are reported in its own section
+    Separate,
+}
+impl FromStr for SubmoduleMode {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "merged" => Ok(Self::Merged),
+            "separate" => Ok(Self::Separate),
+            mode => Err(anyhow::anyhow!(ConvertError::UnknownSubmoduleMode(mode.to_string())))?,
+        }
+    }
+}
+
+/// gitlink entry of the tree, resolved with `.gitmodules` into the url and the pinned commit
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Submodule {
+    pub name: String,
+    pub path: String,
+    pub url: String,
+    pub sha: String,
+}
+impl Submodule {
+    pub const GITMODULES: &'static str = ".gitmodules";
+
+    /// parse `[submodule "name"]` sections of `.gitmodules` into `(name, path, url)`
+    pub fn parse_gitmodules(content: &str) -> Vec<(String, Option<String>, Option<String>)> {
+        let mut sections: Vec<(String, Option<String>, Option<String>)> = Vec::new();
+        for line in content.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with(['#', ';'])) {
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                if let Some(name) = header.trim().strip_prefix("submodule") {
+                    sections.push((name.trim().trim_matches('"').to_string(), None, None));
+                }
+            } else if let (Some((key, value)), Some(section)) = (line.split_once('='), sections.last_mut()) {
+                let value = Some(value.trim().trim_matches('"').to_string());
+                match key.trim() {
+                    "path" => section.1 = value,
+                    "url" => section.2 = value,
+                    _ => (),
+                }
+            }
+        }
+        sections
+    }
+
+    /// gitlinks that are not described by `.gitmodules` are ignored
+    pub fn resolve(gitmodules: &str, gitlinks: &[SubtreeModel]) -> Vec<Self> {
+        Self::parse_gitmodules(gitmodules)
+            .into_iter()
+            .filter_map(|(name, path, url)| {
+                let (path, url) = (path?, url?);
+                let gitlink = gitlinks
+                    .iter()
+                    .find(|subtree| subtree.contents_type == ContentsType::Commit && subtree.path == path)?;
+                Some(Self { name, path, url, sha: gitlink.sha.clone() })
+            })
+            .collect()
+    }
+
+    /// the url is normalized into https, relative urls such as `../lib.git` are resolved from the superproject
+    pub fn normalized_url(&self, superproject: &Url) -> Result<Url> {
+        let url = self.url.trim_end_matches('/').trim_end_matches(".git");
+        let normalized = if url.starts_with("./") || url.starts_with("../") {
+            let base = Url::parse(&format!("{}/", superproject.as_str().trim_end_matches('/')));
+            base.and_then(|base| base.join(url))
+        } else if let Some(ssh) = url.strip_prefix("ssh://") {
+            let (_user, rest) = ssh.split_once('@').unwrap_or(("", ssh));
+            Url::parse(&format!("https://{rest}"))
+        } else if let Some((host, path)) = url.split_once(':').filter(|(host, _)| host.contains('@')) {
+            let (_user, host) = host.split_once('@').unwrap_or(("", host));
+            Url::parse(&format!("https://{host}/{}", path.trim_start_matches('/')))
+        } else {
+            Url::parse(url)
+        };
+        Ok(normalized.map_err(|_| anyhow::anyhow!(UrlParseError::InvalidHost))?)
+    }
+
+    /// submodules on the same host as the superproject inherit its origins and token
+    pub fn repository(&self, superproject: &Repository) -> Result<Repository> {
+        let superproject_url = superproject.to_url()?;
+        let url = self.normalized_url(&superproject_url)?;
+        if url.origin() == superproject_url.origin() {
+            superproject.sibling(&url)
+        } else {
+            Repository::from_url(&url)
+                .map(|repository| repository.with_control(superproject.control()).with_fetcher(superproject.fetcher()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gitlink(path: &str, sha: &str) -> SubtreeModel {
+        let (path, sha) = (path.to_string(), sha.to_string());
+        SubtreeModel {
+            path,
+            mode: "160000".to_string(),
+            contents_type: ContentsType::Commit,
+            sha,
+            size: None,
+            url: None,
+        }
+    }
+
+    #[test]
+    fn test_resolve_gitmodules() {
+        let gitmodules = r#"
+            [submodule "vendor/tokei"]
+                path = vendor/tokei
+                url = https://github.com/XAMPPRocky/tokei.git
+            # comment
+            [submodule "docs"]
+                path = docs
+                url = ../docs.git
+            [submodule "missing"]
+                path = missing
+                url = https://github.com/hayas1/missing
+        "#;
+        let gitlinks = [gitlink("vendor/tokei", "a1b2c3"), gitlink("docs", "d4e5f6")];
+        let submodules = Submodule::resolve(gitmodules, &gitlinks);
+        assert_eq!(submodules.len(), 2);
+        assert_eq!(submodules[0].url, "https://github.com/XAMPPRocky/tokei.git");
+        assert_eq!((&submodules[1].name[..], &submodules[1].sha[..]), ("docs", "d4e5f6"));
+    }
+
+    #[test]
+    fn test_submodule_repository() {
+        let superproject = Repository::new("github", "hayas1", "tokei-toukei").unwrap();
+        let submodule =
+            |url: &str| Submodule { name: "lib".into(), path: "lib".into(), url: url.into(), sha: "".into() };
+
+        for url in [
+            "https://github.com/XAMPPRocky/tokei.git",
+            "git@github.com:XAMPPRocky/tokei.git",
+            "ssh://git@github.com/XAMPPRocky/tokei",
+            "../../XAMPPRocky/tokei.git",
+        ] {
+            let repository = submodule(url).repository(&superproject).unwrap();
+            assert_eq!(
+                (repository.host(), repository.owner(), repository.repo()),
+                ("github".into(), "XAMPPRocky".into(), "tokei".into()),
+                "{url}"
+            );
+        }
+        let repository = submodule("../tokei").repository(&superproject).unwrap();
+        assert_eq!((repository.owner(), repository.repo()), ("hayas1".into(), "tokei".into()));
+
+        assert!(submodule("https://git.example.com/owner/repo").repository(&superproject).is_err());
+    }
+}
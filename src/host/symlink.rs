@@ -0,0 +1,165 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{convert::ConvertError, Error, Result},
+    github::models::{FileMode, SubtreeModel},
+};
+
+use super::statistics::{SkipReason, Skipped};
+
+/// symlinks are skipped by default, because their content is the path of the target
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymlinkMode {
+    #[default]
+    Skip,
+    /// follow the symlinks within the repository, the targets that are already counted are not counted again
+    Follow,
+}
+impl FromStr for SymlinkMode {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "skip" => Ok(Self::Skip),
+            "follow" => Ok(Self::Follow),
+            mode => Err(anyhow::anyhow!(ConvertError::UnknownSymlinkMode(mode.to_string())))?,
+        }
+    }
+}
+
+/// resolve the symlinks of the tree with their targets, which are the contents of the symlinks
+pub struct SymlinkResolver<'a> {
+    entries: HashMap<&'a str, &'a SubtreeModel>,
+    tree: &'a [SubtreeModel],
+    targets: &'a HashMap<String, String>,
+}
+impl<'a> SymlinkResolver<'a> {
+    /// symlinks to symlinks are followed up to this depth
+    pub const MAX_DEPTH: usize = 8;
+
+    pub fn new(tree: &'a [SubtreeModel], targets: &'a HashMap<String, String>) -> Self {
+        let entries = tree.iter().map(|subtree| (&subtree.path[..], subtree)).collect();
+        Self { entries, tree, targets }
+    }
+
+    /// join the target to the directory of the symlink, `None` if it points outside the repository
+    pub fn join(link: &str, target: &str) -> Option<String> {
+        if target.starts_with('/') {
+            return None;
+        }
+        let mut components: Vec<_> = link.split('/').collect();
+        components.pop();
+        for component in target.split('/') {
+            match component {
+                "" | "." => (),
+                ".." => {
+                    components.pop()?;
+                }
+                component => components.push(component),
+            }
+        }
+        Some(components.join("/"))
+    }
+
+    /// regular files that the symlink points to, a directory is expanded into the files under it
+    pub fn resolve(&self, link: &str) -> std::result::Result<Vec<&'a SubtreeModel>, SkipReason> {
+        let mut link = link.to_string();
+        for _ in 0..Self::MAX_DEPTH {
+            let target = self.targets.get(&link).ok_or(SkipReason::UnresolvedSymlink)?;
+            let path = Self::join(&link, target.trim_end()).ok_or(SkipReason::DanglingSymlink)?;
+            let entry = self.entries.get(&path[..]).ok_or(SkipReason::DanglingSymlink)?;
+            match entry.file_mode() {
+                FileMode::Regular | FileMode::Executable => return Ok(vec![entry]),
+                FileMode::Directory => {
+                    let prefix = format!("{path}/");
+                    let files = self.tree.iter().filter(|subtree| {
+                        subtree.path.starts_with(&prefix)
+                            && matches!(subtree.file_mode(), FileMode::Regular | FileMode::Executable)
+                    });
+                    return Ok(files.collect());
+                }
+                FileMode::Symlink => link = path,
+                FileMode::Gitlink | FileMode::Unknown => return Err(SkipReason::DanglingSymlink),
+            }
+        }
+        Err(SkipReason::UnresolvedSymlink)
+    }
+
+    /// files to be counted additionally, `counted` is updated so that no file is counted twice
+    pub fn follow(&self, links: &[String], counted: &mut HashSet<String>) -> (Vec<SubtreeModel>, Vec<Skipped>) {
+        let (mut followed, mut skipped) = (Vec::new(), Vec::new());
+        for link in links {
+            match self.resolve(link) {
+                Ok(files) => {
+                    let files: Vec<_> = files.into_iter().filter(|file| counted.insert(file.path.clone())).collect();
+                    if files.is_empty() {
+                        skipped.push(Skipped::new(link, SkipReason::DuplicateTarget));
+                    }
+                    followed.extend(files.into_iter().cloned());
+                }
+                Err(reason) => skipped.push(Skipped::new(link, reason)),
+            }
+        }
+        (followed, skipped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::github::models::ContentsType;
+
+    fn entry(path: &str, mode: &str) -> SubtreeModel {
+        let contents_type = if mode == "040000" { ContentsType::Tree } else { ContentsType::Blob };
+        let (path, mode, sha) = (path.to_string(), mode.to_string(), path.to_string());
+        SubtreeModel { path, mode, contents_type, sha, size: None, url: None }
+    }
+
+    #[test]
+    fn test_join_target() {
+        assert_eq!(SymlinkResolver::join("docs/README.md", "../README.md"), Some("README.md".to_string()));
+        assert_eq!(SymlinkResolver::join("src/lib", "./vendor/lib"), Some("src/vendor/lib".to_string()));
+        assert_eq!(SymlinkResolver::join("src/lib", "../../outside"), None);
+        assert_eq!(SymlinkResolver::join("src/lib", "/etc/passwd"), None);
+    }
+
+    #[test]
+    fn test_follow_symlinks() {
+        let tree = [
+            entry("src", "040000"),
+            entry("src/main.rs", "100644"),
+            entry("vendor", "040000"),
+            entry("vendor/lib.rs", "100644"),
+            entry("vendor/build.sh", "100755"),
+            entry("main.rs", "120000"),
+            entry("lib", "120000"),
+            entry("link", "120000"),
+            entry("dangling", "120000"),
+        ];
+        let targets: HashMap<_, _> =
+            [("main.rs", "src/main.rs"), ("lib", "vendor"), ("link", "lib"), ("dangling", "nowhere")]
+                .into_iter()
+                .map(|(link, target)| (link.to_string(), target.to_string()))
+                .collect();
+        let resolver = SymlinkResolver::new(&tree, &targets);
+
+        let mut counted: HashSet<_> = ["src/main.rs".to_string()].into();
+        let links: Vec<_> = ["main.rs", "lib", "link", "dangling"].into_iter().map(String::from).collect();
+        let (followed, skipped) = resolver.follow(&links, &mut counted);
+        let followed: Vec<_> = followed.iter().map(|subtree| &subtree.path[..]).collect();
+        assert_eq!(followed, ["vendor/lib.rs", "vendor/build.sh"]);
+        assert_eq!(
+            skipped,
+            [
+                Skipped::new("main.rs", SkipReason::DuplicateTarget),
+                Skipped::new("link", SkipReason::DuplicateTarget),
+                Skipped::new("dangling", SkipReason::DanglingSymlink),
+            ]
+        );
+    }
+}
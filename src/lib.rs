@@ -1,3 +1,10 @@
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
 pub mod components;
 pub mod error;
+pub mod gitea;
 pub mod github;
+pub mod gitlab;
+pub mod host;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod server;
@@ -1,5 +1,10 @@
-use tokei_toukei::components::routes::Main;
-
+#[cfg(target_arch = "wasm32")]
 fn main() {
-    yew::Renderer::<Main>::new().render();
+    yew::Renderer::<tokei_toukei::components::routes::Main>::new().render();
+}
+
+/// the command line shares the statistics engine with the web app
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> std::process::ExitCode {
+    tokei_toukei::cli::main()
 }
@@ -0,0 +1,249 @@
+use std::{
+    net::SocketAddr,
+    path::PathBuf,
+    process::ExitCode,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::Parser;
+use futures::executor::block_on;
+use serde_json::json;
+use url::Url;
+
+use crate::{
+    cli::format::Format,
+    components::query_parameters::{QueryParams, StatisticsParamsModel, TableViewParamsModel},
+    error::{
+        repository::{RateLimitError, ResponseError},
+        server::ServerError,
+        Error, Result,
+    },
+    github::repository::GitHubToken,
+    host::{
+        cache::Cache,
+        fetch::{Fetcher, NativeFetcher},
+        pool::WorkerPool,
+        statistics::{Statistics, WalkOptions},
+        Repository,
+    },
+};
+
+/// serve the statistics of the remote repositories as JSON, for the dashboards that cannot use the web view
+#[derive(Debug, Clone, Eq, PartialEq, Parser)]
+#[command(name = "tokei-toukei-server", version, about)]
+pub struct Args {
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub address: SocketAddr,
+
+    /// directory of the cache keyed by blob SHA, `tokei-toukei` of the temporary directory if it is not given
+    #[arg(long)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// number of the requests that are handled at the same time, the others wait in the queue
+    #[arg(long, default_value_t = 8)]
+    pub workers: usize,
+
+    /// personal access token of github.com
+    #[arg(long, env = "GITHUB_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+}
+
+/// response of the server, whose body is JSON
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: serde_json::Value,
+}
+impl Response {
+    pub fn new(status: u16, body: serde_json::Value) -> Self {
+        Self { status, headers: Vec::new(), body }
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// the body of the error is `{"error": message}`
+    pub fn error(err: &Error) -> Self {
+        let body = json!({ "error": err.to_string() });
+        if let Some(err) = err.downcast_ref::<ServerError>() {
+            let status = match err {
+                ServerError::NotFound(_) => 404,
+                ServerError::MethodNotAllowed(_) => 405,
+                ServerError::BadRequest(_) => 400,
+            };
+            Self::new(status, body)
+        } else if let Some(err) = err.downcast_ref::<RateLimitError>() {
+            let seconds = match err {
+                RateLimitError::Exceeded { reset } => {
+                    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_secs());
+                    reset.saturating_sub(now)
+                }
+                RateLimitError::RetryAfter { seconds } => *seconds,
+            };
+            Self::new(429, body).with_header("Retry-After", &seconds.to_string())
+        } else if let Some(ResponseError::UnexpectedStatus { status: 404, .. }) = err.downcast_ref() {
+            Self::new(404, body)
+        } else {
+            Self::new(502, body)
+        }
+    }
+}
+
+/// `GET /api/{host}/{owner}/{repo}` with the query parameters of the web view, such as `sha`, `paths`, `excluded`,
+/// and `order_by`, nested namespaces of GitLab are also supported, such as `/api/gitlab/group/subgroup/project`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Server {
+    pub fetcher: Fetcher,
+    pub token: Option<GitHubToken>,
+    /// the cache directory should be set before the first request
+    pub cache: bool,
+}
+impl Server {
+    pub async fn respond(&self, method: &str, path_and_query: &str) -> Response {
+        let response = match method {
+            "GET" => self.statistics(path_and_query).await,
+            method => Err(anyhow::anyhow!(ServerError::MethodNotAllowed(method.to_string())).into()),
+        };
+        match response {
+            Ok(body) => Response::new(200, body),
+            Err(err) => Response::error(&err),
+        }
+        .with_header("Content-Type", "application/json")
+        .with_header("Access-Control-Allow-Origin", "*")
+    }
+
+    /// the path is parsed into the repository, and the query into the parameters of the web view
+    pub fn route(&self, path_and_query: &str) -> Result<(Repository, StatisticsParamsModel, TableViewParamsModel)> {
+        let not_found = || anyhow::anyhow!(ServerError::NotFound(path_and_query.to_string()));
+        let url = Url::parse("http://localhost").and_then(|base| base.join(path_and_query)).map_err(|_| not_found())?;
+        let segments: Vec<_> = url.path_segments().ok_or_else(not_found)?.filter(|s| !s.is_empty()).collect();
+        let (host, owner, repo) = match &segments[..] {
+            ["api", host, owner @ .., repo] if !owner.is_empty() => (host, owner.join("/"), repo),
+            _ => Err(not_found())?,
+        };
+        let bad_request = |err: Error| anyhow::anyhow!(ServerError::BadRequest(err.to_string()));
+        let repository = Repository::new(host, &owner, repo).map_err(bad_request)?;
+        let repository = repository.with_token(self.token.clone()).with_fetcher(self.fetcher.clone());
+        let query: Vec<_> = url.query_pairs().into_owned().collect();
+        let params = StatisticsParamsModel::from_query(&query).map_err(bad_request)?;
+        let table = TableViewParamsModel::from_query(&query).map_err(bad_request)?;
+        Ok((repository, params, table))
+    }
+
+    pub async fn statistics(&self, path_and_query: &str) -> Result<serde_json::Value> {
+        let (repository, params, TableViewParamsModel { order_by, .. }) = self.route(path_and_query)?;
+        let filter = params.filter().map_err(|err| anyhow::anyhow!(ServerError::BadRequest(err.to_string())))?;
+        let (options, config) = (WalkOptions { cache: self.cache, ..params.walk_options() }, tokei::Config::default());
+        let statistics = Statistics::get(repository, params.sha.as_deref(), &filter, &config, options).await?;
+        Ok(Format::json(&statistics, order_by))
+    }
+}
+
+fn cache_dir(args: &Args) -> PathBuf {
+    args.cache_dir.clone().unwrap_or_else(|| std::env::temp_dir().join(env!("CARGO_PKG_NAME")))
+}
+
+/// the requests are handled by the fixed number of threads, because the walk blocks its thread until it completes
+pub fn main() -> ExitCode {
+    let args = Args::parse();
+    let directory = cache_dir(&args);
+    if let Err(err) = Cache::set_directory(directory.clone()) {
+        eprintln!("error: {err}");
+        return ExitCode::FAILURE;
+    }
+    let listener = match tiny_http::Server::http(args.address) {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let token = args.token.as_deref().map(GitHubToken::new);
+    let server = Arc::new(Server { fetcher: Fetcher::Native(NativeFetcher), token, cache: true });
+    let pool = WorkerPool::new(args.workers);
+    eprintln!("listening on http://{}, the cache is in {}", args.address, directory.display());
+    for request in listener.incoming_requests() {
+        let server = server.clone();
+        pool.execute(move || {
+            let Response { status, headers, body } = block_on(server.respond(request.method().as_str(), request.url()));
+            let response = headers.iter().fold(
+                tiny_http::Response::from_string(body.to_string()).with_status_code(status),
+                |response, (name, value)| match tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+                    Ok(header) => response.with_header(header),
+                    Err(()) => response,
+                },
+            );
+            if let Err(err) = request.respond(response) {
+                eprintln!("warning: {err}");
+            }
+        });
+    }
+    ExitCode::SUCCESS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::{
+        fetch::{HttpResponse, MockFetcher},
+        RepositoryHost,
+    };
+
+    const API: &str = "https://api.github.com/repos/hayas1/tokei-toukei";
+    const RAW: &str = "https://raw.githubusercontent.com/hayas1/tokei-toukei";
+    const SHA: &str = "4a1e7c3d5b2f80916e0d7a3c1b5f9e8d2c6a4b10";
+
+    fn server() -> Server {
+        let ok = |body: &[u8]| HttpResponse::new(200).with_body(body);
+        let mock = MockFetcher::new()
+            .with_get(&format!("{API}/commits/v1"), ok(include_bytes!("../../tests/fixtures/github/commits.json")))
+            .with_get(
+                &format!("{API}/git/trees/{SHA}?recursive=true"),
+                ok(include_bytes!("../../tests/fixtures/github/trees.json")),
+            )
+            .with_get(&format!("{RAW}/{SHA}/README.md"), ok(b"# tokei-toukei\n\nhello\n"))
+            .with_get(&format!("{RAW}/{SHA}/src/main.rs"), ok(b"fn main() {\n    // hello\n    println!();\n}\n"))
+            .with_get(&format!("{RAW}/{SHA}/logo.png"), ok(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        Server { fetcher: mock.into(), ..Default::default() }
+    }
+
+    #[test]
+    fn test_route() {
+        let server = server();
+        let (repository, params, table) =
+            server.route("/api/gitlab/group/subgroup/project?sha=v1&paths=src/**&order_by=code").unwrap();
+        assert_eq!(repository.to_url().unwrap().as_str(), "https://gitlab.com/group/subgroup/project");
+        assert_eq!((params.sha.as_deref(), params.paths), (Some("v1"), vec!["src/**".to_string()]));
+        assert_eq!(table.order_by, Some(tokei::Sort::Code));
+
+        let status = |path: &str| Response::error(&server.route(path).unwrap_err()).status;
+        assert_eq!(status("/api/github/tokei-toukei"), 404);
+        assert_eq!(status("/statistics/github/hayas1/tokei-toukei"), 404);
+        assert_eq!(status("/api/unknown/hayas1/tokei-toukei"), 400);
+        assert_eq!(status("/api/github/hayas1/tokei-toukei?submodules=unknown"), 400);
+
+        let rate_limited = Response::error(&anyhow::anyhow!(RateLimitError::RetryAfter { seconds: 60 }).into());
+        assert_eq!((rate_limited.status, rate_limited.headers), (429, vec![("Retry-After".into(), "60".into())]));
+    }
+
+    #[test]
+    fn test_respond() {
+        let server = server();
+        let response = block_on(server.respond("GET", "/api/github/hayas1/tokei-toukei?sha=v1&excluded=*.md"));
+        assert_eq!(response.status, 200);
+        assert!(response.headers.contains(&("Access-Control-Allow-Origin".to_string(), "*".to_string())));
+        assert_eq!(response.body["sha"], SHA);
+        let languages = response.body["languages"].as_array().unwrap();
+        assert_eq!(languages.iter().map(|language| &language["language"]).collect::<Vec<_>>(), vec!["Rust"]);
+        assert_eq!(languages[0]["code"], 3);
+
+        let response = block_on(server.respond("GET", "/api/github/hayas1/tokei-toukei?sha=unknown"));
+        assert_eq!(response.status, 404);
+        assert!(response.body["error"].as_str().unwrap().contains("404"));
+        assert_eq!(block_on(server.respond("POST", "/api/github/hayas1/tokei-toukei")).status, 405);
+    }
+}
@@ -0,0 +1,286 @@
+//! `Statistics::get` against the recorded responses of the GitHub API, which runs without the network
+
+use std::collections::BTreeSet;
+
+use futures::executor::block_on;
+use serde_json::json;
+use tokei::LanguageType;
+use tokei_toukei::{
+    error::{
+        repository::{RateLimitError, ResponseError},
+        Result,
+    },
+    github::repository::GitHubRepository,
+    host::{
+        fetch::{HttpResponse, MockFetcher},
+        filter::PathFilter,
+        ignores::IgnoreFilesMode,
+        linguist::Category,
+        statistics::{SkipReason, Skipped, Statistics, WalkOptions},
+        submodule::SubmoduleMode,
+        symlink::SymlinkMode,
+        Repository,
+    },
+};
+
+const API: &str = "https://api.github.com/repos/hayas1/tokei-toukei";
+const RAW: &str = "https://raw.githubusercontent.com/hayas1/tokei-toukei";
+const SHA: &str = "4a1e7c3d5b2f80916e0d7a3c1b5f9e8d2c6a4b10";
+const SRC_SHA: &str = "d3c4e5f6a7b8091a2b3c4d5e6f7a8b9034567890";
+
+const COMMITS: &str = include_str!("fixtures/github/commits.json");
+const TREES: &str = include_str!("fixtures/github/trees.json");
+const TREES_TRUNCATED: &str = include_str!("fixtures/github/trees_truncated.json");
+const TREES_ROOT: &str = include_str!("fixtures/github/trees_root.json");
+const TREES_SRC: &str = include_str!("fixtures/github/trees_src.json");
+
+const README: &str = "# tokei-toukei\n\nhello\n";
+const MAIN: &str = "fn main() {\n    // hello\n    println!();\n}\n";
+const LOGO: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+fn ok(body: impl Into<Vec<u8>>) -> HttpResponse {
+    HttpResponse::new(200).with_body(body)
+}
+
+/// the commit `v1` and its files, the tree is not recorded
+fn recorded() -> MockFetcher {
+    MockFetcher::new()
+        .with_get(&format!("{API}/commits/v1"), ok(COMMITS))
+        .with_get(&format!("{RAW}/{SHA}/README.md"), ok(README))
+        .with_get(&format!("{RAW}/{SHA}/logo.png"), ok(LOGO))
+        .with_get(&format!("{RAW}/{SHA}/src/main.rs"), ok(MAIN))
+}
+
+/// the entry of a regular file in the recorded tree
+fn blob(path: &str, sha: &str) -> serde_json::Value {
+    json!({ "path": path, "mode": "100644", "type": "blob", "sha": sha, "size": 32 })
+}
+
+fn get(mock: MockFetcher, reference: &str) -> Result<Statistics> {
+    get_with(mock, reference, WalkOptions { cache: false, ..Default::default() })
+}
+
+fn get_with(mock: MockFetcher, reference: &str, options: WalkOptions) -> Result<Statistics> {
+    let repository = Repository::GitHub(GitHubRepository::new("hayas1", "tokei-toukei")).with_fetcher(mock.into());
+    let (filter, config) = (PathFilter::default(), tokei::Config::default());
+    block_on(Statistics::get(repository, Some(reference), &filter, &config, options))
+}
+
+fn files(statistics: &Statistics, language_type: LanguageType) -> usize {
+    statistics.languages.get(&language_type).map_or(0, |language| language.reports.len())
+}
+
+#[test]
+fn test_statistics() {
+    let mock = recorded().with_get(&format!("{API}/git/trees/{SHA}?recursive=true"), ok(TREES));
+    let statistics = get(mock, "v1").unwrap();
+    assert_eq!((statistics.commit.sha.as_str(), statistics.commit.message.as_str()), (SHA, "Release v1"));
+    assert!(!statistics.truncated);
+
+    assert_eq!((files(&statistics, LanguageType::Rust), files(&statistics, LanguageType::Markdown)), (1, 1));
+    let rust = statistics.languages[&LanguageType::Rust].summarise();
+    assert_eq!((rust.code, rust.comments, rust.blanks), (3, 1, 0));
+
+    assert_eq!(statistics.skipped, vec![Skipped::new("logo.png", SkipReason::Binary)]);
+    // `src/gone.rs` is not recorded, so it is responded with 404
+    assert_eq!(statistics.failures.len(), 1);
+    assert!(statistics.failures[0].contains("404"));
+}
+
+#[test]
+fn test_truncated_tree() {
+    let mock = recorded()
+        .with_get(&format!("{API}/git/trees/{SHA}?recursive=true"), ok(TREES_TRUNCATED))
+        .with_get(&format!("{API}/git/trees/{SHA}?recursive=false"), ok(TREES_ROOT))
+        .with_get(&format!("{API}/git/trees/{SRC_SHA}?recursive=false"), ok(TREES_SRC));
+    let statistics = get(mock, "v1").unwrap();
+
+    // the tree is listed level by level, and every level is complete
+    assert!(!statistics.truncated);
+    assert_eq!((files(&statistics, LanguageType::Rust), files(&statistics, LanguageType::Markdown)), (1, 1));
+    assert!(statistics.failures.is_empty());
+}
+
+#[test]
+fn test_not_found() {
+    let err = get(recorded(), "unknown").unwrap_err();
+    match err.downcast_ref::<ResponseError>() {
+        Some(ResponseError::UnexpectedStatus { status, url }) => {
+            assert_eq!((*status, url.as_str()), (404, &format!("{API}/commits/unknown")[..]));
+        }
+        None => panic!("should be 404, but {err}"),
+    }
+}
+
+#[test]
+fn test_rate_limit() {
+    let exceeded =
+        HttpResponse::new(403).with_header("X-RateLimit-Remaining", "0").with_header("X-RateLimit-Reset", "1717171717");
+    let mock = recorded()
+        .with_get(&format!("{API}/git/trees/{SHA}?recursive=true"), ok(TREES))
+        .with_get(&format!("{RAW}/{SHA}/src/main.rs"), exceeded.clone());
+    let err = get(mock, "v1").unwrap_err();
+    assert_eq!(err.downcast_ref::<RateLimitError>(), Some(&RateLimitError::Exceeded { reset: 1717171717 }));
+
+    let mock = recorded().with_get(&format!("{API}/commits/v1"), exceeded);
+    let err = get(mock, "v1").unwrap_err();
+    assert_eq!(err.downcast_ref::<RateLimitError>(), Some(&RateLimitError::Exceeded { reset: 1717171717 }));
+}
+
+#[test]
+fn test_gitattributes() {
+    let tree = json!({
+        "sha": SHA,
+        "url": format!("{API}/git/trees/{SHA}"),
+        "tree": [
+            blob(".gitattributes", "1a2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d"),
+            blob("api/user.pb.go", "2b3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e"),
+            blob("src/main.rs", "f5e6a7b8c9d0213b4c5d6e7f8a9b0c1256789012"),
+            blob("src/table.inc", "3c4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f"),
+        ],
+        "truncated": false,
+    });
+    let mock = recorded()
+        .with_json(&format!("{API}/git/trees/{SHA}?recursive=true"), &tree)
+        .with_get(
+            &format!("{RAW}/{SHA}/.gitattributes"),
+            ok("*.pb.go linguist-generated\n*.inc linguist-language=C++\n"),
+        )
+        .with_get(&format!("{RAW}/{SHA}/api/user.pb.go"), ok("package api\n\ntype User struct{}\n"))
+        .with_get(&format!("{RAW}/{SHA}/src/table.inc"), ok("int table[] = {0};\n"));
+    let statistics = get(mock, "v1").unwrap();
+
+    // generated files are not counted, and the language is overridden, as GitHub does
+    let counted = |languages: &tokei::Languages| {
+        let files = |t| languages.get(&t).map_or(0, |language: &tokei::Language| language.reports.len());
+        (files(LanguageType::Go), files(LanguageType::Cpp), files(LanguageType::Rust))
+    };
+    assert_eq!(counted(&statistics.languages), (0, 1, 1));
+    assert_eq!(statistics.classified.len(), 2);
+    assert_eq!(counted(&statistics.categorized(&Category::shown_by_default())), (0, 1, 1));
+
+    let shown = Category::shown(&[Category::Generated, Category::Overridden]);
+    assert_eq!(shown, BTreeSet::from([Category::Generated]));
+    assert_eq!(counted(&statistics.categorized(&shown)), (1, 0, 1));
+}
+
+#[test]
+fn test_ignore_files() {
+    let tree = json!({
+        "sha": SHA,
+        "url": format!("{API}/git/trees/{SHA}"),
+        "tree": [
+            blob(".gitignore", "4d5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f70"),
+            blob("src/main.rs", "f5e6a7b8c9d0213b4c5d6e7f8a9b0c1256789012"),
+            blob("target/debug/build.rs", "5e6f708192a3b4c5d6e7f8091a2b3c4d5e6f7081"),
+            blob("web/.tokeignore", "6f708192a3b4c5d6e7f8091a2b3c4d5e6f708192"),
+            blob("web/dist/app.js", "708192a3b4c5d6e7f8091a2b3c4d5e6f708192a3"),
+            blob("web/index.js", "8192a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4"),
+        ],
+        "truncated": false,
+    });
+    let mock = recorded()
+        .with_json(&format!("{API}/git/trees/{SHA}?recursive=true"), &tree)
+        .with_get(&format!("{RAW}/{SHA}/.gitignore"), ok("target/\n"))
+        .with_get(&format!("{RAW}/{SHA}/target/debug/build.rs"), ok("fn main() {}\n"))
+        .with_get(&format!("{RAW}/{SHA}/web/.tokeignore"), ok("/dist\n"))
+        .with_get(&format!("{RAW}/{SHA}/web/dist/app.js"), ok("console.log(1);\n"))
+        .with_get(&format!("{RAW}/{SHA}/web/index.js"), ok("console.log(0);\n"));
+
+    // the ignore files are disregarded by default, because they are not applied to the files in git
+    let statistics = get(mock.clone(), "v1").unwrap();
+    assert_eq!((files(&statistics, LanguageType::Rust), files(&statistics, LanguageType::JavaScript)), (2, 2));
+
+    let options = WalkOptions { ignore_files: IgnoreFilesMode::Respect, cache: false, ..Default::default() };
+    let statistics = get_with(mock.clone(), "v1", options).unwrap();
+    assert_eq!((files(&statistics, LanguageType::Rust), files(&statistics, LanguageType::JavaScript)), (1, 1));
+    assert_eq!(
+        statistics.skipped,
+        vec![
+            Skipped::new("target/debug/build.rs", SkipReason::Ignored),
+            Skipped::new("web/dist/app.js", SkipReason::Ignored),
+        ]
+    );
+
+    // the ignore file that cannot be fetched is not applied, and it is reported as a failure
+    let mock = mock.with_get(&format!("{RAW}/{SHA}/web/.tokeignore"), HttpResponse::new(500));
+    let statistics = get_with(mock, "v1", options).unwrap();
+    assert_eq!(files(&statistics, LanguageType::JavaScript), 2);
+    assert!(!statistics.failures.is_empty());
+    assert!(statistics.failures.iter().all(|failure| failure.contains("web/.tokeignore")));
+}
+
+#[test]
+fn test_broken_submodule() {
+    let tree = json!({
+        "sha": SHA,
+        "url": format!("{API}/git/trees/{SHA}"),
+        "tree": [
+            { "path": ".gitmodules", "mode": "100644", "type": "blob", "sha": "92a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5", "size": 96 },
+            { "path": "src/main.rs", "mode": "100644", "type": "blob", "sha": "f5e6a7b8c9d0213b4c5d6e7f8a9b0c1256789012", "size": 48 },
+            { "path": "vendor/missing", "mode": "160000", "type": "commit", "sha": "a3b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d6" },
+        ],
+        "truncated": false,
+    });
+    let gitmodules = "[submodule \"missing\"]\n\tpath = vendor/missing\n\turl = https://github.com/hayas1/missing\n";
+    let mock = recorded()
+        .with_json(&format!("{API}/git/trees/{SHA}?recursive=true"), &tree)
+        .with_get(&format!("{RAW}/{SHA}/.gitmodules"), ok(gitmodules));
+    let options = WalkOptions { submodules: Some(SubmoduleMode::Merged), cache: false, ..Default::default() };
+    let statistics = get_with(mock, "v1", options).unwrap();
+
+    // the submodule that cannot be counted does not fail the statistics of the superproject
+    assert_eq!(files(&statistics, LanguageType::Rust), 1);
+    assert_eq!(statistics.submodules.len(), 1);
+    assert!(statistics.submodules[0].statistics.as_ref().unwrap_err().contains("404"));
+}
+
+#[test]
+fn test_max_file_size_without_sizes() {
+    let tree = json!({
+        "sha": SHA,
+        "url": format!("{API}/git/trees/{SHA}"),
+        "tree": [
+            { "path": "README.md", "mode": "100644", "type": "blob", "sha": "a1b2c3d4e5f60718293a4b5c6d7e8f9012345678" },
+            { "path": "src/main.rs", "mode": "100644", "type": "blob", "sha": "f5e6a7b8c9d0213b4c5d6e7f8a9b0c1256789012" },
+        ],
+        "truncated": false,
+    });
+    let mock = recorded().with_json(&format!("{API}/git/trees/{SHA}?recursive=true"), &tree);
+    let options = WalkOptions { max_file_size: Some(32), cache: false, ..Default::default() };
+    let statistics = get_with(mock, "v1", options).unwrap();
+
+    // the sizes are not in the tree, so the files are skipped by the fetched bytes
+    assert_eq!(files(&statistics, LanguageType::Markdown), 1);
+    assert_eq!(files(&statistics, LanguageType::Rust), 0);
+    assert_eq!(statistics.skipped, vec![Skipped::new("src/main.rs", SkipReason::TooLarge)]);
+}
+
+#[test]
+fn test_follow_symlinks() {
+    let tree = json!({
+        "sha": SHA,
+        "url": format!("{API}/git/trees/{SHA}"),
+        "tree": [
+            { "path": "bin/main.rs", "mode": "120000", "type": "blob", "sha": "b4c5d6e7f8091a2b3c4d5e6f708192a3b4c5d6e7", "size": 14 },
+            { "path": "src/main.rs", "mode": "100644", "type": "blob", "sha": "f5e6a7b8c9d0213b4c5d6e7f8a9b0c1256789012", "size": 48 },
+        ],
+        "truncated": false,
+    });
+    let mock = recorded()
+        .with_json(&format!("{API}/git/trees/{SHA}?recursive=true"), &tree)
+        .with_get(&format!("{RAW}/{SHA}/bin/main.rs"), ok("../src/main.rs"));
+    let options = WalkOptions { symlinks: SymlinkMode::Follow, cache: false, ..Default::default() };
+    let statistics = get_with(mock.clone(), "v1", options).unwrap();
+
+    // the target of the symlink is resolved by fetching the link by itself, and it is not counted twice
+    assert_eq!(files(&statistics, LanguageType::Rust), 1);
+    assert_eq!(statistics.skipped, vec![Skipped::new("bin/main.rs", SkipReason::DuplicateTarget)]);
+
+    // the symlink that cannot be fetched is reported, and the walk goes on
+    let mock = mock.with_get(&format!("{RAW}/{SHA}/bin/main.rs"), HttpResponse::new(500));
+    let statistics = get_with(mock, "v1", options).unwrap();
+    assert_eq!(files(&statistics, LanguageType::Rust), 1);
+    assert_eq!(statistics.skipped, vec![Skipped::new("bin/main.rs", SkipReason::UnresolvedSymlink)]);
+    assert_eq!(statistics.failures.len(), 1);
+}